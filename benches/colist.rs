@@ -0,0 +1,71 @@
+//! Compares keeping a [`CoList`] up to date as items are pushed with reading it again from all of its transactions.
+//!
+//! Run with `cargo bench --bench colist`.
+
+use dashmap::DashMap;
+use jazz_rs::{
+    covalue::{
+        colist::CoList,
+        common::Ruleset,
+        header::CoValueHeader,
+        session::{SessionWriter, VerifiedState},
+    },
+    crypto::agent::AgentSecret,
+    id::{rawcoid::RawCoID, session_id::SessionID},
+};
+use serde_json::json;
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+/// The number of items in the list before timing begins.
+const ITEMS: usize = 50_000;
+/// The number of items added to the list in each transaction before timing begins.
+const BATCH: usize = 1_000;
+/// The number of items pushed and read back while timing the incremental updates.
+const PUSHES: usize = 50;
+/// The number of items pushed and read back while timing the full rebuilds, which are much slower.
+const REBUILDS: usize = 5;
+
+fn main() -> anyhow::Result<()> {
+    let agent_secret = AgentSecret::new_random();
+    let writer = SessionWriter::new(
+        SessionID::new_random(agent_secret.agent_id()),
+        agent_secret.signer_secret().clone(),
+    );
+    let header = CoValueHeader::new("colist", Ruleset::UnsafeAllowAll, None);
+    let state: VerifiedState = VerifiedState::new(&RawCoID::new([0; 19]), &header, &DashMap::new());
+    let mut list = CoList::new(state)?;
+    let start = Instant::now();
+    for batch in 0..ITEMS / BATCH {
+        list.extend(&writer, (0..BATCH).map(|x| json!(batch * BATCH + x)))?;
+    }
+    black_box(list.len());
+    report("Batched inserts", ITEMS / BATCH, start.elapsed());
+
+    let mut incremental = list.clone();
+    let start = Instant::now();
+    for x in 0..PUSHES {
+        incremental.push(&writer, x)?;
+        black_box(incremental.get(incremental.len() - 1));
+    }
+    report("Incremental", PUSHES, start.elapsed());
+
+    let mut rebuilt = list;
+    let start = Instant::now();
+    for x in 0..REBUILDS {
+        rebuilt.push(&writer, x)?;
+        rebuilt = CoList::new(rebuilt.state().clone())?;
+        black_box(rebuilt.get(rebuilt.len() - 1));
+    }
+    report("Full rebuild", REBUILDS, start.elapsed());
+    Ok(())
+}
+
+fn report(name: &str, iterations: usize, elapsed: Duration) {
+    println!(
+        "{name}: {iterations} iterations in {elapsed:?} ({:?} per iteration)",
+        elapsed / iterations as u32
+    );
+}
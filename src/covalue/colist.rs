@@ -0,0 +1,989 @@
+use super::{
+    common::RawCoValue,
+    session::{SessionWriter, Transaction, ValidTransaction, VerifiedState},
+};
+use crate::{
+    crypto::{
+        encrypt::KeySecret,
+        hash::Hash,
+        provider::{CryptoProvider, NativeCrypto},
+        sign::Signature,
+        streaming_hash::StreamingHash,
+    },
+    id::{common::TransactionID, key_id::KeyID, session_id::SessionID, signer_id::SignerID},
+    sync::common::SyncMessage,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::OnceLock,
+};
+
+/// Identifies a change by the transaction it was made in and its index in that transaction.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "camelCase")]
+pub struct OpID {
+    #[serde(flatten)]
+    pub tx_id: TransactionID,
+    pub change_idx: usize,
+}
+
+impl OpID {
+    pub fn new(tx_id: TransactionID, change_idx: usize) -> Self {
+        Self { tx_id, change_idx }
+    }
+}
+
+/// An [`OpID`] with its session ID left as a string, so that changes can be read without parsing the same session ID again for each.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct UnparsedOpID {
+    #[serde(rename = "sessionID")]
+    session_id: String,
+    tx_index: usize,
+    change_idx: usize,
+}
+
+/// A change to a [`CoList`], referring to items by their [`OpID`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "op", rename_all = "lowercase")]
+#[serde(bound(serialize = "I: Serialize", deserialize = "I: Deserialize<'de>"))]
+pub enum ListOpPayload<I = OpID> {
+    /// Inserts an item after another, or at the start of the list if `after` is `None`.
+    App {
+        value: Value,
+        #[serde(with = "anchor::start")]
+        after: Option<I>,
+    },
+    /// Inserts an item before another, or at the end of the list if `before` is `None`.
+    Pre {
+        value: Value,
+        #[serde(with = "anchor::end")]
+        before: Option<I>,
+    },
+    /// Deletes the item inserted by the given change.
+    Del { insertion: I },
+}
+
+/// (De)serializes the item an insertion is anchored on, where `None` is an edge of the list, written as `start` or `end`.
+mod anchor {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error};
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Anchor<I> {
+        Op(I),
+        Edge(String),
+    }
+
+    fn serialize<S: Serializer, I: Serialize>(
+        anchor: &Option<I>,
+        edge: &str,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match anchor {
+            Some(op_id) => op_id.serialize(serializer),
+            None => serializer.serialize_str(edge),
+        }
+    }
+
+    fn deserialize<'de, D: Deserializer<'de>, I: Deserialize<'de>>(
+        deserializer: D,
+        edge: &str,
+    ) -> Result<Option<I>, D::Error> {
+        match Anchor::deserialize(deserializer)? {
+            Anchor::Op(op_id) => Ok(Some(op_id)),
+            Anchor::Edge(x) if x == edge => Ok(None),
+            Anchor::Edge(x) => Err(D::Error::custom(format!(
+                "Anchor `{x}` not valid; expected an op ID or `{edge}`"
+            ))),
+        }
+    }
+
+    pub mod start {
+        use super::*;
+
+        pub fn serialize<S: Serializer, I: Serialize>(
+            anchor: &Option<I>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            super::serialize(anchor, "start", serializer)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>, I: Deserialize<'de>>(
+            deserializer: D,
+        ) -> Result<Option<I>, D::Error> {
+            super::deserialize(deserializer, "start")
+        }
+    }
+
+    pub mod end {
+        use super::*;
+
+        pub fn serialize<S: Serializer, I: Serialize>(
+            anchor: &Option<I>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            super::serialize(anchor, "end", serializer)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>, I: Deserialize<'de>>(
+            deserializer: D,
+        ) -> Result<Option<I>, D::Error> {
+            super::deserialize(deserializer, "end")
+        }
+    }
+}
+
+/// Where an item was inserted, relative to another item or an edge of the list.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Anchor {
+    After(Option<OpID>),
+    Before(Option<OpID>),
+}
+
+impl Anchor {
+    /// The item anchored on, if not an edge of the list.
+    fn op_id(&self) -> Option<&OpID> {
+        match self {
+            Anchor::After(x) | Anchor::Before(x) => x.as_ref(),
+        }
+    }
+}
+
+/// An item inserted into a [`CoList`], along with the items inserted next to it.
+#[derive(Debug, Clone, PartialEq)]
+struct Insertion {
+    value: Value,
+    made_at: u64,
+    anchor: Anchor,
+    /// Items inserted directly before this one, from earliest to latest.
+    predecessors: Vec<OpID>,
+    /// Items inserted directly after this one, from earliest to latest.
+    successors: Vec<OpID>,
+}
+
+/// A list of JSON values, where each item is inserted before or after another item, so that concurrent insertions are merged without conflicts.
+///
+/// Of the items inserted next to the same item, the latest insertion is placed closest to it.
+/// Insertions made at the same time are ordered by their [`OpID`], so every peer arrives at the same order.
+///
+/// The list keeps the items it has read between reads, and reads only the transactions added since when its [`CoValue`] changes.
+/// The order of the items is worked out when first needed, then kept up to date as items are added and deleted.
+#[derive(Debug, Clone)]
+pub struct CoList<C: CryptoProvider = NativeCrypto> {
+    state: VerifiedState<C>,
+    read_keys: HashMap<KeyID, KeySecret>,
+    /// The time the list is read as of, if it is not read as it is now.
+    as_of: Option<u64>,
+    /// The number of transactions already read from each session.
+    read: HashMap<SessionID, usize>,
+    /// The session IDs op IDs have referred to, by their string form.
+    session_ids: HashMap<String, SessionID>,
+    insertions: HashMap<OpID, Insertion>,
+    /// Items inserted at the start of the list, from earliest to latest.
+    after_start: Vec<OpID>,
+    /// Items inserted at the end of the list, from earliest to latest.
+    before_end: Vec<OpID>,
+    /// Items anchored on an item not yet read, by the ID of that item.
+    orphans: HashMap<OpID, Vec<OpID>>,
+    /// Items that have been deleted.
+    deletions: HashSet<OpID>,
+    /// The items that have not been deleted, in order, once worked out.
+    order: OnceLock<Vec<OpID>>,
+}
+
+impl<C: CryptoProvider> RawCoValue for CoList<C> {
+    const HEADER_TYPE: &'static str = "colist";
+}
+
+impl<C: CryptoProvider> CoList<C> {
+    /// Reads a [`CoValue`] as a list, checking that its header is that of a list.
+    pub fn new(state: VerifiedState<C>) -> anyhow::Result<Self> {
+        Self::with_read_keys(state, HashMap::new())
+    }
+
+    /// Reads a [`CoValue`] as a list, decrypting its private transactions with the given keys.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The verified transactions of the [`CoValue`].
+    ///
+    /// * `read_keys` - The keys private transactions may be encrypted with; transactions encrypted with other keys are ignored.
+    pub fn with_read_keys(
+        state: VerifiedState<C>,
+        read_keys: HashMap<KeyID, KeySecret>,
+    ) -> anyhow::Result<Self> {
+        state.header().check_type::<Self>(state.id())?;
+        Ok(Self::read(state, read_keys, None))
+    }
+
+    /// The list as it was at a given time, built from only the transactions made at or before then.
+    ///
+    /// The list returned is read-only and never updates; inserting or deleting items in it, or adding content to it, fails.
+    ///
+    /// # Arguments
+    ///
+    /// * `time` - The time to read the list as of, in milliseconds since the Unix epoch.
+    pub fn at_time(&self, time: u64) -> Self {
+        Self::read(self.state.clone(), self.read_keys.clone(), Some(time))
+    }
+
+    /// The time the list is read as of, if it was made with [`CoList::at_time`].
+    pub fn as_of(&self) -> Option<u64> {
+        self.as_of
+    }
+
+    fn read(
+        state: VerifiedState<C>,
+        read_keys: HashMap<KeyID, KeySecret>,
+        as_of: Option<u64>,
+    ) -> Self {
+        let mut list = Self {
+            state,
+            read_keys,
+            as_of,
+            read: HashMap::new(),
+            session_ids: HashMap::new(),
+            insertions: HashMap::new(),
+            after_start: Vec::new(),
+            before_end: Vec::new(),
+            orphans: HashMap::new(),
+            deletions: HashSet::new(),
+            order: OnceLock::new(),
+        };
+        list.update();
+        list
+    }
+
+    /// Reads the transactions added to the list since it was last read.
+    fn update(&mut self) {
+        let transactions = self.state.valid_transactions_since(
+            &self.read_keys,
+            &self.read,
+            self.as_of.unwrap_or(u64::MAX),
+        );
+        self.read = self
+            .state
+            .known_state_uncached()
+            .sessions
+            .into_iter()
+            .collect();
+        let mut inserted = Vec::new();
+        let mut deleted = Vec::new();
+        for ValidTransaction {
+            tx_id,
+            made_at,
+            changes,
+        } in transactions
+        {
+            for (change_idx, change) in changes.into_iter().enumerate() {
+                let Some(payload) = serde_json::from_value::<ListOpPayload<UnparsedOpID>>(change)
+                    .ok()
+                    .and_then(|x| self.parse_payload(x).ok())
+                else {
+                    continue;
+                };
+                let op_id = OpID::new(tx_id.clone(), change_idx);
+                let (value, anchor) = match payload {
+                    ListOpPayload::App { value, after } => (value, Anchor::After(after)),
+                    ListOpPayload::Pre { value, before } => (value, Anchor::Before(before)),
+                    ListOpPayload::Del { insertion } => {
+                        if self.deletions.insert(insertion.clone()) {
+                            deleted.push(insertion);
+                        }
+                        continue;
+                    }
+                };
+                self.insertions.insert(
+                    op_id.clone(),
+                    Insertion {
+                        value,
+                        made_at,
+                        anchor,
+                        predecessors: Vec::new(),
+                        successors: Vec::new(),
+                    },
+                );
+                inserted.push(op_id);
+            }
+        }
+        for op_id in &inserted {
+            self.link(op_id);
+        }
+        if let Some(mut order) = self.order.take()
+            && self.update_order(&mut order, &inserted, &deleted)
+        {
+            self.order = OnceLock::from(order);
+        }
+    }
+
+    /// Parses the op IDs a change refers to, reusing the session IDs already parsed.
+    fn parse_payload(
+        &mut self,
+        payload: ListOpPayload<UnparsedOpID>,
+    ) -> anyhow::Result<ListOpPayload> {
+        Ok(match payload {
+            ListOpPayload::App { value, after } => ListOpPayload::App {
+                value,
+                after: after.map(|x| self.parse_op_id(x)).transpose()?,
+            },
+            ListOpPayload::Pre { value, before } => ListOpPayload::Pre {
+                value,
+                before: before.map(|x| self.parse_op_id(x)).transpose()?,
+            },
+            ListOpPayload::Del { insertion } => ListOpPayload::Del {
+                insertion: self.parse_op_id(insertion)?,
+            },
+        })
+    }
+
+    fn parse_op_id(&mut self, op_id: UnparsedOpID) -> anyhow::Result<OpID> {
+        let session_id = match self.session_ids.get(&op_id.session_id) {
+            Some(session_id) => session_id.clone(),
+            None => {
+                let session_id: SessionID = op_id.session_id.parse()?;
+                self.session_ids
+                    .insert(op_id.session_id, session_id.clone());
+                session_id
+            }
+        };
+        Ok(OpID::new(
+            TransactionID::new(session_id, op_id.tx_index),
+            op_id.change_idx,
+        ))
+    }
+
+    /// Items are placed next to each other by when they were made, then by their ID.
+    fn sort_key<'a>(&'a self, op_id: &'a OpID) -> (u64, &'a OpID) {
+        (self.insertions[op_id].made_at, op_id)
+    }
+
+    /// The items placed next to an anchor, if it has been read.
+    fn neighbours(&self, anchor: &Anchor) -> Option<&Vec<OpID>> {
+        match anchor {
+            Anchor::After(None) => Some(&self.after_start),
+            Anchor::Before(None) => Some(&self.before_end),
+            Anchor::After(Some(x)) => self.insertions.get(x).map(|x| &x.successors),
+            Anchor::Before(Some(x)) => self.insertions.get(x).map(|x| &x.predecessors),
+        }
+    }
+
+    fn neighbours_mut(&mut self, anchor: &Anchor) -> Option<&mut Vec<OpID>> {
+        match anchor {
+            Anchor::After(None) => Some(&mut self.after_start),
+            Anchor::Before(None) => Some(&mut self.before_end),
+            Anchor::After(Some(x)) => self.insertions.get_mut(x).map(|x| &mut x.successors),
+            Anchor::Before(Some(x)) => self.insertions.get_mut(x).map(|x| &mut x.predecessors),
+        }
+    }
+
+    /// Places an item next to its anchor, along with any items waiting for it.
+    ///
+    /// An item anchored on one not yet read, such as one made later by a peer with a faster clock, waits until that item is read.
+    fn link(&mut self, op_id: &OpID) {
+        let anchor = self.insertions[op_id].anchor.clone();
+        let Some(neighbours) = self.neighbours(&anchor) else {
+            if let Some(x) = anchor.op_id() {
+                self.orphans
+                    .entry(x.clone())
+                    .or_default()
+                    .push(op_id.clone());
+            }
+            return;
+        };
+        let key = self.sort_key(op_id);
+        let index = neighbours.partition_point(|x| self.sort_key(x) < key);
+        if let Some(neighbours) = self.neighbours_mut(&anchor) {
+            neighbours.insert(index, op_id.clone());
+        }
+        for orphan in self.orphans.remove(op_id).unwrap_or_default() {
+            self.link(&orphan);
+        }
+    }
+
+    /// The items that have not been deleted, in order, visiting each of the given items along with the items placed around it.
+    fn visit<'a>(&'a self, items: impl DoubleEndedIterator<Item = &'a OpID>) -> Vec<OpID> {
+        let mut order = Vec::new();
+        // Items are visited in the reverse of the order they are pushed, and emitted when popped a second time.
+        let mut todo: Vec<(&OpID, bool)> = items.rev().map(|x| (x, false)).collect();
+        while let Some((op_id, expanded)) = todo.pop() {
+            let Some(insertion) = self.insertions.get(op_id) else {
+                continue;
+            };
+            if expanded {
+                if !self.deletions.contains(op_id) {
+                    order.push(op_id.clone());
+                }
+                continue;
+            }
+            todo.extend(insertion.successors.iter().map(|x| (x, false)));
+            todo.push((op_id, true));
+            todo.extend(insertion.predecessors.iter().rev().map(|x| (x, false)));
+        }
+        order
+    }
+
+    /// The items that have not been deleted, in order.
+    fn order(&self) -> &[OpID] {
+        self.order
+            .get_or_init(|| self.visit(self.after_start.iter().rev().chain(self.before_end.iter())))
+    }
+
+    /// Updates a known order of the items with newly read insertions and deletions.
+    ///
+    /// Insertions placed closest to their anchor, as local edits and most edits by peers are, are spliced into the order with the items placed around them, one splice for each anchor.
+    ///
+    /// # Returns
+    ///
+    /// Whether the order could be updated; if not, it must be worked out again.
+    fn update_order(&self, order: &mut Vec<OpID>, inserted: &[OpID], deleted: &[OpID]) -> bool {
+        for op_id in deleted {
+            if let Some(index) = order.iter().rposition(|x| x == op_id) {
+                order.remove(index);
+            }
+        }
+        let inserted: HashSet<&OpID> = inserted.iter().collect();
+        let mut anchors = HashSet::new();
+        for op_id in &inserted {
+            let anchor = &self.insertions[*op_id].anchor;
+            // Items anchored on other new items are visited along with them.
+            if anchor.op_id().is_some_and(|x| inserted.contains(x)) || !anchors.insert(anchor) {
+                continue;
+            }
+            // Items still waiting for their anchor are not in the list yet.
+            let Some(neighbours) = self.neighbours(anchor) else {
+                continue;
+            };
+            let first_new = neighbours
+                .iter()
+                .position(|x| inserted.contains(x))
+                .unwrap_or(neighbours.len());
+            if !neighbours[first_new..].iter().all(|x| inserted.contains(x)) {
+                return false;
+            }
+            let (index, items) = match anchor {
+                Anchor::After(after) => (
+                    match after {
+                        Some(x) => match order.iter().rposition(|y| y == x) {
+                            Some(index) => index + 1,
+                            None => return false,
+                        },
+                        None => 0,
+                    },
+                    self.visit(neighbours[first_new..].iter().rev()),
+                ),
+                Anchor::Before(before) => (
+                    match before {
+                        Some(x) => match order.iter().rposition(|y| y == x) {
+                            Some(index) => index,
+                            None => return false,
+                        },
+                        None => order.len(),
+                    },
+                    self.visit(neighbours[first_new..].iter()),
+                ),
+            };
+            order.splice(index..index, items);
+        }
+        true
+    }
+
+    /// The verified transactions the list is read from.
+    pub fn state(&self) -> &VerifiedState<C> {
+        &self.state
+    }
+
+    /// Adds content received from a peer, then reads the transactions added.
+    pub fn try_add_new_content(
+        &mut self,
+        new_content: &SyncMessage,
+        signer_for_session: impl Fn(&SessionID) -> anyhow::Result<SignerID> + Sync,
+    ) -> anyhow::Result<()> {
+        self.check_current()?;
+        let result = self
+            .state
+            .try_add_new_content(new_content, signer_for_session);
+        self.update();
+        result
+    }
+
+    /// Adds transactions to a session, then reads the transactions added.
+    ///
+    /// See [`VerifiedState::try_add_transactions`].
+    #[allow(clippy::too_many_arguments)]
+    pub fn try_add_transactions(
+        &mut self,
+        session_id: &SessionID,
+        signer_id: &SignerID,
+        new_transactions: &[Transaction],
+        given_expected_new_hash: &Option<Hash>,
+        new_signature: &Signature,
+        skip_verify: &Option<bool>,
+        given_new_streaming_hash: &Option<StreamingHash<C>>,
+    ) -> anyhow::Result<()> {
+        self.check_current()?;
+        let result = self.state.try_add_transactions(
+            session_id,
+            signer_id,
+            new_transactions,
+            given_expected_new_hash,
+            new_signature,
+            skip_verify,
+            given_new_streaming_hash,
+        );
+        self.update();
+        result
+    }
+
+    /// The number of items in the list.
+    pub fn len(&self) -> usize {
+        self.order().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The item at an index, if the list is long enough.
+    pub fn get(&self, index: usize) -> Option<&Value> {
+        Some(&self.insertions[self.order().get(index)?].value)
+    }
+
+    /// The items of the list, in order.
+    pub fn items(&self) -> impl Iterator<Item = &Value> {
+        self.order().iter().map(|x| &self.insertions[x].value)
+    }
+
+    /// The list as a JSON array.
+    pub fn as_array(&self) -> Vec<Value> {
+        self.items().cloned().collect()
+    }
+
+    /// Inserts an item at an index in a new transaction, shifting the items from that index onwards.
+    pub fn insert(
+        &mut self,
+        writer: &SessionWriter,
+        index: usize,
+        value: impl Into<Value>,
+    ) -> anyhow::Result<()> {
+        self.insert_many(writer, index, [value.into()])
+    }
+
+    /// Inserts items at an index in a single transaction, shifting the items from that index onwards.
+    pub fn insert_many(
+        &mut self,
+        writer: &SessionWriter,
+        index: usize,
+        values: impl IntoIterator<Item = Value>,
+    ) -> anyhow::Result<()> {
+        let order = self.order();
+        let anchor = match index {
+            _ if index > order.len() => return Err(self.out_of_bounds(index, order.len())),
+            0 => match order.first() {
+                Some(first) => Anchor::Before(Some(first.clone())),
+                None => Anchor::After(None),
+            },
+            _ => Anchor::After(Some(order[index - 1].clone())),
+        };
+        let mut payloads: Vec<_> = values
+            .into_iter()
+            .map(|value| match &anchor {
+                Anchor::After(after) => ListOpPayload::App {
+                    value,
+                    after: after.clone(),
+                },
+                Anchor::Before(before) => ListOpPayload::Pre {
+                    value,
+                    before: before.clone(),
+                },
+            })
+            .collect();
+        if payloads.is_empty() {
+            return Ok(());
+        }
+        // The latest of the items inserted after an item is placed closest to it, so they are inserted last to first.
+        if let Anchor::After(_) = anchor {
+            payloads.reverse();
+        }
+        self.make_transaction(writer, &payloads)
+    }
+
+    /// Adds an item to the end of the list in a new transaction.
+    pub fn push(&mut self, writer: &SessionWriter, value: impl Into<Value>) -> anyhow::Result<()> {
+        self.insert(writer, self.len(), value)
+    }
+
+    /// Adds items to the end of the list in a single transaction.
+    pub fn extend(
+        &mut self,
+        writer: &SessionWriter,
+        values: impl IntoIterator<Item = Value>,
+    ) -> anyhow::Result<()> {
+        self.insert_many(writer, self.len(), values)
+    }
+
+    /// Deletes the item at an index in a new transaction.
+    pub fn delete(&mut self, writer: &SessionWriter, index: usize) -> anyhow::Result<()> {
+        let insertion = self.op_id_at(index)?;
+        self.make_transaction(writer, &[ListOpPayload::Del { insertion }])
+    }
+
+    /// Replaces the item at an index in a new transaction, by inserting the new item after it and deleting it.
+    pub fn replace(
+        &mut self,
+        writer: &SessionWriter,
+        index: usize,
+        value: impl Into<Value>,
+    ) -> anyhow::Result<()> {
+        let insertion = self.op_id_at(index)?;
+        self.make_transaction(
+            writer,
+            &[
+                ListOpPayload::App {
+                    value: value.into(),
+                    after: Some(insertion.clone()),
+                },
+                ListOpPayload::Del { insertion },
+            ],
+        )
+    }
+
+    fn op_id_at(&self, index: usize) -> anyhow::Result<OpID> {
+        let order = self.order();
+        order
+            .get(index)
+            .cloned()
+            .ok_or_else(|| self.out_of_bounds(index, order.len()))
+    }
+
+    fn out_of_bounds(&self, index: usize, len: usize) -> anyhow::Error {
+        anyhow::anyhow!(
+            "Index {index} out of bounds for list {} of length {len}",
+            self.state.id()
+        )
+    }
+
+    fn make_transaction(
+        &mut self,
+        writer: &SessionWriter,
+        payloads: &[ListOpPayload],
+    ) -> anyhow::Result<()> {
+        self.check_current()?;
+        let changes = payloads
+            .iter()
+            .map(serde_json::to_value)
+            .collect::<Result<Vec<_>, _>>()?;
+        self.state.make_transaction(writer, &changes)?;
+        self.update();
+        Ok(())
+    }
+
+    /// Fails if the list is read as of an earlier time, as such lists are never updated.
+    fn check_current(&self) -> anyhow::Result<()> {
+        match self.as_of {
+            Some(as_of) => Err(anyhow::anyhow!(
+                "List {} is read as of {as_of}; lists read as of an earlier time cannot be changed",
+                self.state.id()
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::covalue::{
+        comap::tests::{new_group, new_owned_state},
+        common::Ruleset,
+        header::CoValueHeader,
+        session::tests::{all_content, new_writer, signer_for_session},
+    };
+    use dashmap::DashMap;
+    use proptest::prelude::*;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    fn new_list() -> CoList {
+        let header = CoValueHeader::new("colist", Ruleset::UnsafeAllowAll, None);
+        CoList::new(VerifiedState::new(
+            &header.id().unwrap(),
+            &header,
+            &DashMap::new(),
+        ))
+        .unwrap()
+    }
+
+    /// A peer of a list that has not received any of its content.
+    fn new_peer(list: &CoList) -> CoList {
+        CoList::new(VerifiedState::new(
+            list.state.id(),
+            list.state.header(),
+            &DashMap::new(),
+        ))
+        .unwrap()
+    }
+
+    /// Makes a change in a new transaction at the given time.
+    fn apply(list: &mut CoList, writer: &SessionWriter, made_at: u64, payload: ListOpPayload) {
+        list.state
+            .make_transaction_at(writer, made_at, &[serde_json::to_value(payload).unwrap()])
+            .unwrap();
+        list.update();
+    }
+
+    fn app(value: &str, after: Option<&OpID>) -> ListOpPayload {
+        ListOpPayload::App {
+            value: value.into(),
+            after: after.cloned(),
+        }
+    }
+
+    fn pre(value: &str, before: Option<&OpID>) -> ListOpPayload {
+        ListOpPayload::Pre {
+            value: value.into(),
+            before: before.cloned(),
+        }
+    }
+
+    /// The content of a single session of a list.
+    fn session_content(list: &CoList, writer: &SessionWriter) -> SyncMessage {
+        let content = all_content(&list.state);
+        if let SyncMessage::NewContentMessage { new, .. } = &content {
+            new.retain(|session_id, _| session_id == writer.session_id());
+        }
+        content
+    }
+
+    /// Sends a peer the content of a list it does not know yet.
+    fn sync(from: &CoList, to: &mut CoList) {
+        let known_state = to.state.known_state_uncached();
+        for piece in from
+            .state
+            .clone()
+            .new_content_since(&Some(known_state))
+            .unwrap_or_default()
+        {
+            to.try_add_new_content(&piece, signer_for_session).unwrap();
+        }
+    }
+
+    /// Checks that two peers have the same items in the same order, and that reading them afresh gives that order too.
+    fn assert_converged(a: &CoList, b: &CoList) {
+        assert_eq!(a.as_array(), b.as_array());
+        assert_eq!(
+            CoList::new(a.state.clone()).unwrap().as_array(),
+            a.as_array()
+        );
+        assert_eq!(
+            CoList::new(b.state.clone()).unwrap().as_array(),
+            b.as_array()
+        );
+    }
+
+    fn prepend(state: &mut VerifiedState, writer: &SessionWriter, made_at: u64, value: &str) {
+        let payload: ListOpPayload = ListOpPayload::App {
+            value: value.into(),
+            after: None,
+        };
+        state
+            .make_transaction_at(writer, made_at, &[serde_json::to_value(payload).unwrap()])
+            .unwrap();
+    }
+
+    #[test]
+    fn checks_changes_against_roles_at_the_time() {
+        let (admin, writer, outsider) = (new_writer(), new_writer(), new_writer());
+        let group = Arc::new(new_group(&admin, &writer));
+        let mut state = new_owned_state("colist", &group);
+        prepend(&mut state, &writer, 1, "before joining");
+        prepend(&mut state, &writer, 3, "as a writer");
+        prepend(&mut state, &outsider, 3, "never a member");
+        prepend(&mut state, &writer, 5, "after being revoked");
+        prepend(&mut state, &admin, 5, "as an admin");
+
+        // Without its group, the list's changes are not checked.
+        assert_eq!(CoList::new(state.clone()).unwrap().len(), 5);
+        let list = CoList::new(state.with_group(group)).unwrap();
+        assert_eq!(
+            list.as_array(),
+            [json!("as an admin"), json!("as a writer")]
+        );
+        assert_eq!(list.at_time(4).as_array(), [json!("as a writer")]);
+        assert!(list.at_time(2).is_empty());
+    }
+
+    #[test]
+    fn concurrent_insertions_on_the_same_anchor_converge() {
+        let (a, b) = (new_writer(), new_writer());
+        let mut list_a = new_list();
+        apply(&mut list_a, &a, 1, app("x", None));
+        let mut list_b = new_peer(&list_a);
+        sync(&list_a, &mut list_b);
+        let x = list_a.op_id_at(0).unwrap();
+
+        // Both peers insert around the same item at the same time, so only their op IDs order the insertions.
+        for (list, writer, name) in [(&mut list_a, &a, "a"), (&mut list_b, &b, "b")] {
+            apply(list, writer, 2, app(&format!("{name} after x"), Some(&x)));
+            apply(list, writer, 2, pre(&format!("{name} before x"), Some(&x)));
+            apply(list, writer, 2, app(&format!("{name} at start"), None));
+            apply(list, writer, 2, pre(&format!("{name} at end"), None));
+        }
+        sync(&list_a, &mut list_b);
+        sync(&list_b, &mut list_a);
+
+        assert_eq!(list_a.len(), 9);
+        assert_converged(&list_a, &list_b);
+    }
+
+    #[test]
+    fn deletion_of_an_item_not_yet_known_applies_once_it_arrives() {
+        let (a, b) = (new_writer(), new_writer());
+        let mut list_a = new_list();
+        apply(&mut list_a, &a, 1, app("x", None));
+        apply(&mut list_a, &a, 2, app("y", None));
+        let mut list_b = new_peer(&list_a);
+        sync(&list_a, &mut list_b);
+        let x = list_b.op_id_at(1).unwrap();
+        apply(&mut list_b, &b, 3, ListOpPayload::Del { insertion: x });
+
+        let mut list_c = new_peer(&list_a);
+        list_c
+            .try_add_new_content(&session_content(&list_b, &b), signer_for_session)
+            .unwrap();
+        assert!(list_c.is_empty());
+        list_c
+            .try_add_new_content(&session_content(&list_a, &a), signer_for_session)
+            .unwrap();
+
+        assert_eq!(list_c.as_array(), [json!("y")]);
+        assert_converged(&list_b, &list_c);
+    }
+
+    #[test]
+    fn items_arriving_before_their_anchor_are_placed_once_it_arrives() {
+        let (a, b) = (new_writer(), new_writer());
+        let mut list_a = new_list();
+        apply(&mut list_a, &a, 1, app("x", None));
+        let mut list_b = new_peer(&list_a);
+        sync(&list_a, &mut list_b);
+        let x = list_b.op_id_at(0).unwrap();
+        apply(&mut list_b, &b, 2, app("y", Some(&x)));
+        let y = list_b.op_id_at(1).unwrap();
+        apply(&mut list_b, &b, 3, pre("z", Some(&y)));
+
+        let mut list_c = new_peer(&list_a);
+        list_c
+            .try_add_new_content(&session_content(&list_b, &b), signer_for_session)
+            .unwrap();
+        assert!(list_c.is_empty());
+        list_c
+            .try_add_new_content(&session_content(&list_a, &a), signer_for_session)
+            .unwrap();
+
+        assert_eq!(list_c.as_array(), [json!("x"), json!("z"), json!("y")]);
+        assert_converged(&list_b, &list_c);
+    }
+
+    #[test]
+    fn peers_exchanging_new_content_converge() {
+        let (a, b) = (new_writer(), new_writer());
+        let mut list_a = new_list();
+        list_a.extend(&a, [json!("a1"), json!("a2")]).unwrap();
+        let mut list_b = new_peer(&list_a);
+        sync(&list_a, &mut list_b);
+
+        list_a.push(&a, "a3").unwrap();
+        // The peer has seen only some of the first peer's transactions when it makes its own.
+        sync(&list_a, &mut list_b);
+        list_a.insert(&a, 1, "a4").unwrap();
+        list_a.delete(&a, 0).unwrap();
+        list_b.insert(&b, 0, "b1").unwrap();
+        list_b.replace(&b, 2, "b2").unwrap();
+        list_b.delete(&b, 3).unwrap();
+        sync(&list_a, &mut list_b);
+        sync(&list_b, &mut list_a);
+
+        assert_converged(&list_a, &list_b);
+        assert_eq!(
+            list_a
+                .state
+                .known_state_uncached()
+                .sessions
+                .into_iter()
+                .collect::<HashMap<_, _>>(),
+            list_b
+                .state
+                .known_state_uncached()
+                .sessions
+                .into_iter()
+                .collect::<HashMap<_, _>>()
+        );
+        // Nothing is left to send either way.
+        for (from, to) in [(&list_a, &list_b), (&list_b, &list_a)] {
+            let known_state = to.state.known_state_uncached();
+            assert!(
+                from.state
+                    .clone()
+                    .new_content_since(&Some(known_state))
+                    .is_none()
+            );
+        }
+    }
+
+    #[test]
+    fn list_read_as_of_a_time_is_never_updated() {
+        let (a, b) = (new_writer(), new_writer());
+        let mut list_a = new_list();
+        apply(&mut list_a, &a, 1, app("x", None));
+        let mut list_b = new_peer(&list_a);
+        apply(&mut list_b, &b, 1, app("y", None));
+
+        let mut old = list_a.at_time(1);
+        assert!(old.push(&a, "z").is_err());
+        assert!(
+            old.try_add_new_content(&session_content(&list_b, &b), signer_for_session)
+                .is_err()
+        );
+        assert_eq!(old.as_array(), [json!("x")]);
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(32))]
+
+        #[test]
+        fn incremental_order_matches_reading_afresh(
+            steps in prop::collection::vec((0..3usize, 0..4u8, any::<usize>()), 1..40)
+        ) {
+            let writers = [new_writer(), new_writer(), new_writer()];
+            let mut lists = vec![new_list()];
+            lists.push(new_peer(&lists[0]));
+            lists.push(new_peer(&lists[0]));
+            for (peer, action, x) in steps {
+                let len = lists[peer].len();
+                match action {
+                    0 | 1 => lists[peer].insert(&writers[peer], x % (len + 1), x).unwrap(),
+                    2 if len > 0 => lists[peer].delete(&writers[peer], x % len).unwrap(),
+                    // Receives a single session, in whatever order sessions happen to arrive.
+                    _ => {
+                        let content = session_content(&lists[x % 3], &writers[x / 3 % 3]);
+                        lists[peer].try_add_new_content(&content, signer_for_session).unwrap();
+                    }
+                }
+                let list = &lists[peer];
+                prop_assert_eq!(
+                    list.as_array(),
+                    CoList::new(list.state.clone()).unwrap().as_array()
+                );
+            }
+            for (from, to) in [(0, 1), (1, 2), (2, 0), (0, 1)] {
+                let from = lists[from].clone();
+                sync(&from, &mut lists[to]);
+            }
+            prop_assert_eq!(lists[0].as_array(), lists[1].as_array());
+            prop_assert_eq!(lists[1].as_array(), lists[2].as_array());
+        }
+    }
+}
@@ -0,0 +1,581 @@
+use super::{
+    common::RawCoValue,
+    session::{SessionWriter, ValidTransaction, VerifiedState},
+};
+use crate::{
+    crypto::{
+        encrypt::KeySecret,
+        provider::{CryptoProvider, NativeCrypto},
+    },
+    id::{
+        common::{RawAccountID, TransactionID},
+        key_id::KeyID,
+        session_id::SessionID,
+        signer_id::SignerID,
+    },
+    sync::common::SyncMessage,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap};
+
+/// A change to a key of a [`CoMap`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum MapOpPayload {
+    /// Sets the key to a value.
+    Set { key: String, value: Value },
+    /// Deletes the key.
+    Del { key: String },
+}
+
+impl MapOpPayload {
+    pub fn key(&self) -> &str {
+        match self {
+            MapOpPayload::Set { key, .. } => key,
+            MapOpPayload::Del { key } => key,
+        }
+    }
+}
+
+/// A change to a [`CoMap`], along with when and where it was made.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MapOp {
+    pub tx_id: TransactionID,
+    /// Timestamp of the transaction the change was made in.
+    pub made_at: u64,
+    /// Index of the change in its transaction.
+    pub change_idx: usize,
+    pub payload: MapOpPayload,
+}
+
+impl MapOp {
+    /// Changes are ordered by when they were made, then by their transaction, then by their position in the transaction.
+    fn order_key(&self) -> (u64, &TransactionID, usize) {
+        (self.made_at, &self.tx_id, self.change_idx)
+    }
+}
+
+/// An edit to a key of a [`CoMap`], with who made it and when.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MapEdit<'a> {
+    /// The value the key was set to, or `None` if it was deleted.
+    pub value: Option<&'a Value>,
+    /// The account that made the edit, or `None` if it was made in a session owned by an agent rather than an account.
+    pub by: Option<&'a RawAccountID>,
+    pub tx_id: &'a TransactionID,
+    /// Timestamp of the transaction the edit was made in.
+    pub made_at: u64,
+}
+
+impl<'a> From<&'a MapOp> for MapEdit<'a> {
+    fn from(op: &'a MapOp) -> Self {
+        Self {
+            value: match &op.payload {
+                MapOpPayload::Set { value, .. } => Some(value),
+                MapOpPayload::Del { .. } => None,
+            },
+            by: op.tx_id.session_id().raw_account_id(),
+            tx_id: &op.tx_id,
+            made_at: op.made_at,
+        }
+    }
+}
+
+/// A map from strings to JSON values, where each key takes the value of the latest change made to it.
+///
+/// Changes made at the same time are ordered by their [`TransactionID`], so every peer resolves concurrent changes the same way.
+#[derive(Debug, Clone)]
+pub struct CoMap<C: CryptoProvider = NativeCrypto> {
+    state: VerifiedState<C>,
+    read_keys: HashMap<KeyID, KeySecret>,
+    /// The time the map is read as of, if it is not read as it is now.
+    as_of: Option<u64>,
+    /// The changes made to each key, from earliest to latest.
+    ops: BTreeMap<String, Vec<MapOp>>,
+}
+
+impl<C: CryptoProvider> RawCoValue for CoMap<C> {
+    const HEADER_TYPE: &'static str = "comap";
+}
+
+impl<C: CryptoProvider> CoMap<C> {
+    /// Reads a [`CoValue`] as a map, checking that its header is that of a map.
+    pub fn new(state: VerifiedState<C>) -> anyhow::Result<Self> {
+        Self::with_read_keys(state, HashMap::new())
+    }
+
+    /// Reads a [`CoValue`] as a map, decrypting its private transactions with the given keys.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The verified transactions of the [`CoValue`].
+    ///
+    /// * `read_keys` - The keys private transactions may be encrypted with; transactions encrypted with other keys are ignored.
+    pub fn with_read_keys(
+        state: VerifiedState<C>,
+        read_keys: HashMap<KeyID, KeySecret>,
+    ) -> anyhow::Result<Self> {
+        state.header().check_type::<Self>(state.id())?;
+        let mut map = Self {
+            state,
+            read_keys,
+            as_of: None,
+            ops: BTreeMap::new(),
+        };
+        map.rebuild();
+        Ok(map)
+    }
+
+    /// The map as it was at a given time, built from only the transactions made at or before then.
+    ///
+    /// The map returned is read-only and never updates; setting or deleting keys in it, or adding content to it, fails.
+    ///
+    /// # Arguments
+    ///
+    /// * `time` - The time to read the map as of, in milliseconds since the Unix epoch.
+    pub fn at_time(&self, time: u64) -> Self {
+        let mut map = Self {
+            state: self.state.clone(),
+            read_keys: self.read_keys.clone(),
+            as_of: Some(time),
+            ops: BTreeMap::new(),
+        };
+        map.rebuild();
+        map
+    }
+
+    /// The time the map is read as of, if it was made with [`CoMap::at_time`].
+    pub fn as_of(&self) -> Option<u64> {
+        self.as_of
+    }
+
+    /// Rereads all of the transactions of the map.
+    fn rebuild(&mut self) {
+        self.ops.clear();
+        for transaction in self
+            .state
+            .valid_transactions_until(&self.read_keys, self.as_of.unwrap_or(u64::MAX))
+        {
+            self.add_transaction(transaction);
+        }
+    }
+
+    /// Adds the changes of a transaction to the map, skipping changes that are not map operations.
+    fn add_transaction(&mut self, transaction: ValidTransaction) {
+        let ValidTransaction {
+            tx_id,
+            made_at,
+            changes,
+        } = transaction;
+        for (change_idx, change) in changes.into_iter().enumerate() {
+            let Ok(payload) = serde_json::from_value::<MapOpPayload>(change) else {
+                continue;
+            };
+            let op = MapOp {
+                tx_id: tx_id.clone(),
+                made_at,
+                change_idx,
+                payload,
+            };
+            let ops = self.ops.entry(op.payload.key().to_owned()).or_default();
+            let index = ops.partition_point(|x| x.order_key() < op.order_key());
+            ops.insert(index, op);
+        }
+    }
+
+    /// The verified transactions the map is read from.
+    pub fn state(&self) -> &VerifiedState<C> {
+        &self.state
+    }
+
+    /// Adds content received from a peer, then rereads the map.
+    pub fn try_add_new_content(
+        &mut self,
+        new_content: &SyncMessage,
+        signer_for_session: impl Fn(&SessionID) -> anyhow::Result<SignerID> + Sync,
+    ) -> anyhow::Result<()> {
+        self.check_current()?;
+        let result = self
+            .state
+            .try_add_new_content(new_content, signer_for_session);
+        self.rebuild();
+        result
+    }
+
+    /// The changes made to a key, from earliest to latest.
+    pub fn ops(&self, key: &str) -> &[MapOp] {
+        self.ops.get(key).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Every edit made to a key, from earliest to latest.
+    pub fn edits(&self, key: &str) -> impl Iterator<Item = MapEdit<'_>> {
+        self.ops(key).iter().map(MapEdit::from)
+    }
+
+    /// The latest edit made to a key, if it was ever edited.
+    pub fn last_edit(&self, key: &str) -> Option<MapEdit<'_>> {
+        self.ops(key).last().map(MapEdit::from)
+    }
+
+    /// The `n`th edit made to a key, counting from zero at the earliest.
+    pub fn nth_edit(&self, key: &str, n: usize) -> Option<MapEdit<'_>> {
+        self.ops(key).get(n).map(MapEdit::from)
+    }
+
+    /// The latest value of a key, if it is set.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match &self.ops.get(key)?.last()?.payload {
+            MapOpPayload::Set { value, .. } => Some(value),
+            MapOpPayload::Del { .. } => None,
+        }
+    }
+
+    /// The keys that are set, in order.
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.entries().map(|(key, _)| key)
+    }
+
+    /// The keys that are set along with their latest values, in order of key.
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &Value)> {
+        self.ops
+            .iter()
+            .filter_map(|(key, ops)| match &ops.last()?.payload {
+                MapOpPayload::Set { value, .. } => Some((key.as_str(), value)),
+                MapOpPayload::Del { .. } => None,
+            })
+    }
+
+    /// The map as a JSON object.
+    pub fn as_object(&self) -> Map<String, Value> {
+        self.entries()
+            .map(|(key, value)| (key.to_owned(), value.clone()))
+            .collect()
+    }
+
+    /// Sets a key to a value in a new transaction.
+    pub fn set(
+        &mut self,
+        writer: &SessionWriter,
+        key: impl Into<String>,
+        value: impl Into<Value>,
+    ) -> anyhow::Result<()> {
+        self.make_transaction(
+            writer,
+            MapOpPayload::Set {
+                key: key.into(),
+                value: value.into(),
+            },
+        )
+    }
+
+    /// Deletes a key in a new transaction.
+    pub fn delete(&mut self, writer: &SessionWriter, key: impl Into<String>) -> anyhow::Result<()> {
+        self.make_transaction(writer, MapOpPayload::Del { key: key.into() })
+    }
+
+    fn make_transaction(
+        &mut self,
+        writer: &SessionWriter,
+        payload: MapOpPayload,
+    ) -> anyhow::Result<()> {
+        self.check_current()?;
+        let read = self
+            .state
+            .known_state_uncached()
+            .sessions
+            .into_iter()
+            .collect();
+        self.state
+            .make_transaction(writer, &[serde_json::to_value(payload)?])?;
+        // The new transaction is read like any other, so it is ignored if its writer may not make it or it cannot be decrypted.
+        for transaction in self
+            .state
+            .valid_transactions_since(&self.read_keys, &read, u64::MAX)
+        {
+            self.add_transaction(transaction);
+        }
+        Ok(())
+    }
+
+    /// Fails if the map is read as of an earlier time, as such maps are never updated.
+    fn check_current(&self) -> anyhow::Result<()> {
+        match self.as_of {
+            Some(as_of) => Err(anyhow::anyhow!(
+                "Map {} is read as of {as_of}; maps read as of an earlier time cannot be changed",
+                self.state.id()
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::{
+        covalue::{
+            common::Ruleset,
+            header::CoValueHeader,
+            session::tests::{all_content, new_state, new_writer, signer_for_session},
+        },
+        crypto::agent::AgentSecret,
+        id::rawcoid::RawCoID,
+    };
+    use dashmap::DashMap;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    fn member(writer: &SessionWriter) -> String {
+        writer.session_id().owner().to_string()
+    }
+
+    fn set_role(
+        group: &mut VerifiedState,
+        by: &SessionWriter,
+        made_at: u64,
+        writer: &SessionWriter,
+        role: &str,
+    ) {
+        group
+            .make_transaction_at(
+                by,
+                made_at,
+                &[json!({"op": "set", "key": member(writer), "value": role})],
+            )
+            .unwrap();
+    }
+
+    /// A group made by `admin` at time 1, in which `writer` is a writer from time 2 until it is revoked at time 4.
+    pub(crate) fn new_group(admin: &SessionWriter, writer: &SessionWriter) -> VerifiedState {
+        let header = CoValueHeader::new(
+            "comap",
+            Ruleset::Group {
+                initial_admin: admin.session_id().owner().clone(),
+            },
+            None,
+        );
+        let mut group = VerifiedState::new(&header.id().unwrap(), &header, &DashMap::new());
+        set_role(&mut group, admin, 1, admin, "admin");
+        set_role(&mut group, admin, 2, writer, "writer");
+        set_role(&mut group, admin, 4, writer, "revoked");
+        group
+    }
+
+    /// A new [`CoValue`] of the given type owned by a group.
+    pub(crate) fn new_owned_state(type_: &str, group: &VerifiedState) -> VerifiedState {
+        let header = CoValueHeader::new(
+            type_,
+            Ruleset::OwnedByGroup {
+                group: group.id().clone(),
+            },
+            None,
+        );
+        VerifiedState::new(&header.id().unwrap(), &header, &DashMap::new())
+    }
+
+    fn set(state: &mut VerifiedState, writer: &SessionWriter, made_at: u64, key: &str) {
+        state
+            .make_transaction_at(
+                writer,
+                made_at,
+                &[json!({"op": "set", "key": key, "value": made_at})],
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn reads_group_roles_as_of_time() {
+        let (admin, writer, outsider) = (new_writer(), new_writer(), new_writer());
+        let mut group = new_group(&admin, &writer);
+        // Only admins may change the group.
+        set_role(&mut group, &writer, 3, &outsider, "admin");
+
+        let map = CoMap::new(group).unwrap();
+        assert_eq!(map.get(&member(&admin)), Some(&json!("admin")));
+        assert_eq!(map.get(&member(&writer)), Some(&json!("revoked")));
+        assert_eq!(map.get(&member(&outsider)), None);
+        assert_eq!(map.at_time(3).get(&member(&writer)), Some(&json!("writer")));
+        assert_eq!(map.at_time(1).get(&member(&writer)), None);
+    }
+
+    #[test]
+    fn checks_changes_against_roles_at_the_time() {
+        let (admin, writer, outsider) = (new_writer(), new_writer(), new_writer());
+        let group = Arc::new(new_group(&admin, &writer));
+        let mut state = new_owned_state("comap", &group);
+        set(&mut state, &writer, 1, "before joining");
+        set(&mut state, &writer, 3, "as a writer");
+        set(&mut state, &outsider, 3, "never a member");
+        set(&mut state, &writer, 5, "after being revoked");
+        set(&mut state, &admin, 5, "as an admin");
+
+        // Without its group, the map's changes are not checked.
+        assert_eq!(CoMap::new(state.clone()).unwrap().keys().count(), 5);
+        let map = CoMap::new(state.with_group(group)).unwrap();
+        assert_eq!(
+            map.keys().collect::<Vec<_>>(),
+            ["as a writer", "as an admin"]
+        );
+        assert_eq!(map.at_time(4).keys().collect::<Vec<_>>(), ["as a writer"]);
+        assert_eq!(map.at_time(2).keys().count(), 0);
+    }
+
+    #[test]
+    fn latest_change_wins_by_time_made() {
+        let (a, b) = (new_writer(), new_writer());
+        let mut state = new_state();
+        set(&mut state, &a, 2, "key");
+        set(&mut state, &b, 1, "key");
+
+        let mut map = CoMap::new(state).unwrap();
+        assert_eq!(map.get("key"), Some(&json!(2)));
+        map.set(&b, "key", 3).unwrap();
+        assert_eq!(map.get("key"), Some(&json!(3)));
+        assert_eq!(map.ops("key").len(), 3);
+    }
+
+    #[test]
+    fn changes_made_at_the_same_time_are_ordered_by_transaction() {
+        let (a, b) = (new_writer(), new_writer());
+        let mut state = new_state();
+        for writer in [&a, &b] {
+            state
+                .make_transaction_at(
+                    writer,
+                    1,
+                    &[json!({"op": "set", "key": "key", "value": member(writer)})],
+                )
+                .unwrap();
+        }
+
+        let map = CoMap::new(state).unwrap();
+        let [first, second] = map.ops("key") else {
+            panic!("Expected two changes");
+        };
+        assert!(first.tx_id < second.tx_id);
+        let winner = [&a, &b]
+            .into_iter()
+            .find(|x| x.session_id() == second.tx_id.session_id())
+            .unwrap();
+        assert_eq!(map.get("key"), Some(&json!(member(winner))));
+    }
+
+    #[test]
+    fn deleted_key_is_not_set() {
+        let writer = new_writer();
+        let mut map = CoMap::new(new_state()).unwrap();
+        map.set(&writer, "key", "value").unwrap();
+        map.set(&writer, "other", "value").unwrap();
+        map.delete(&writer, "key").unwrap();
+        assert_eq!(map.get("key"), None);
+        assert_eq!(map.keys().collect::<Vec<_>>(), ["other"]);
+        assert_eq!(
+            map.as_object(),
+            json!({"other": "value"}).as_object().cloned().unwrap()
+        );
+        assert_eq!(map.ops("key").len(), 2);
+
+        map.set(&writer, "key", "again").unwrap();
+        assert_eq!(map.get("key"), Some(&json!("again")));
+    }
+
+    #[test]
+    fn own_changes_are_checked_like_any_other() {
+        let (admin, writer, outsider) = (new_writer(), new_writer(), new_writer());
+        let group = Arc::new(new_group(&admin, &writer));
+        let mut map = CoMap::new(new_owned_state("comap", &group).with_group(group)).unwrap();
+        map.set(&outsider, "by an outsider", 1).unwrap();
+        map.set(&admin, "by an admin", 1).unwrap();
+        assert_eq!(map.keys().collect::<Vec<_>>(), ["by an admin"]);
+
+        let (key_secret, key_id) = KeySecret::new_random();
+        let private_writer = new_writer().with_key(key_id, key_secret.clone());
+        let mut map = CoMap::new(new_state()).unwrap();
+        map.set(&private_writer, "key", 1).unwrap();
+        assert_eq!(map.get("key"), None);
+        let map = CoMap::with_read_keys(map.state().clone(), HashMap::from([(key_id, key_secret)]))
+            .unwrap();
+        assert_eq!(map.get("key"), Some(&json!(1)));
+    }
+
+    /// A writer in a new session of an account.
+    fn new_account_writer() -> SessionWriter {
+        let agent_secret = AgentSecret::new_random();
+        SessionWriter::new(
+            SessionID::new_random(RawAccountID::new(RawCoID::new([1; 19]))),
+            agent_secret.signer_secret().clone(),
+        )
+    }
+
+    #[test]
+    fn edits_are_ordered_by_time_then_transaction() {
+        let (agent, account) = (new_writer(), new_account_writer());
+        let mut state = new_state();
+        set(&mut state, &agent, 3, "key");
+        set(&mut state, &account, 1, "key");
+        set(&mut state, &agent, 1, "key");
+        state
+            .make_transaction_at(&account, 3, &[json!({"op": "del", "key": "key"})])
+            .unwrap();
+
+        let map = CoMap::new(state).unwrap();
+        let edits = map.edits("key").collect::<Vec<_>>();
+        assert_eq!(
+            edits.iter().map(|x| x.made_at).collect::<Vec<_>>(),
+            [1, 1, 3, 3]
+        );
+        assert!(edits[0].tx_id < edits[1].tx_id);
+        assert!(edits[2].tx_id < edits[3].tx_id);
+        for edit in &edits {
+            let deleted = edit.tx_id == &TransactionID::new(account.session_id().clone(), 1);
+            assert_eq!(edit.value, (!deleted).then_some(&json!(edit.made_at)));
+            assert_eq!(edit.by, edit.tx_id.session_id().raw_account_id());
+            let by_account = edit.tx_id.session_id() == account.session_id();
+            assert_eq!(edit.by.is_some(), by_account);
+        }
+        for (n, edit) in edits.iter().enumerate() {
+            assert_eq!(map.nth_edit("key", n).as_ref(), Some(edit));
+        }
+        assert_eq!(map.nth_edit("key", 4), None);
+    }
+
+    #[test]
+    fn last_edit_is_the_current_value() {
+        let writer = new_account_writer();
+        let mut map = CoMap::new(new_state()).unwrap();
+        assert_eq!(map.last_edit("key"), None);
+        assert_eq!(map.edits("key").count(), 0);
+
+        map.set(&writer, "key", "value").unwrap();
+        let last_edit = map.last_edit("key").unwrap();
+        assert_eq!(last_edit.value, map.get("key"));
+        assert_eq!(last_edit.by, writer.session_id().raw_account_id());
+        assert!(last_edit.by.is_some());
+
+        map.delete(&writer, "key").unwrap();
+        let last_edit = map.last_edit("key").unwrap();
+        assert_eq!(last_edit.value, None);
+        assert_eq!(map.get("key"), None);
+        assert_eq!(map.edits("key").count(), 2);
+        assert_eq!(map.nth_edit("key", 2), None);
+    }
+
+    #[test]
+    fn map_read_as_of_a_time_is_never_updated() {
+        let (a, b) = (new_writer(), new_writer());
+        let mut state = new_state();
+        set(&mut state, &a, 1, "a");
+        let mut peer: VerifiedState =
+            VerifiedState::new(state.id(), state.header(), &DashMap::new());
+        set(&mut peer, &b, 1, "b");
+
+        let mut old = CoMap::new(state).unwrap().at_time(1);
+        assert!(old.set(&a, "c", 1).is_err());
+        assert!(
+            old.try_add_new_content(&all_content(&peer), signer_for_session)
+                .is_err()
+        );
+        assert_eq!(old.keys().collect::<Vec<_>>(), ["a"]);
+    }
+}
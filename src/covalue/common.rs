@@ -1,6 +1,8 @@
-use crate::id::{common::RawAccountID, rawcoid::RawCoID};
-use chrono::{DateTime, Utc};
+use crate::id::{rawcoid::RawCoID, session_id::SessionOwner};
+use chrono::{DateTime, SubsecRound, Utc};
+use rand_core::{OsRng, RngCore};
 use serde::{Deserialize, Serialize};
+use std::fmt::Display;
 
 pub enum CoValueType {
     CoMap,
@@ -16,17 +18,69 @@ pub enum CoValueType {
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct CoValueUniqueness {
-    uniqueness: String,
-    created_at: DateTime<Utc>,
+    /// When the [`CoValue`] was created, or `None` if it is derived from its creator alone, as accounts are.
+    #[serde(with = "js_date")]
+    created_at: Option<DateTime<Utc>>,
+    uniqueness: Option<String>,
+}
+
+impl CoValueUniqueness {
+    pub fn new(uniqueness: String, created_at: DateTime<Utc>) -> Self {
+        Self {
+            created_at: Some(created_at),
+            uniqueness: Some(uniqueness),
+        }
+    }
+
+    /// Creates a random uniqueness value, as generated by cojson, for a [`CoValue`] created now.
+    ///
+    /// The creation time is truncated to milliseconds, the precision it is sent with.
+    pub fn new_random() -> Self {
+        let mut bytes = [0u8; 12];
+        OsRng.fill_bytes(&mut bytes);
+        Self {
+            created_at: Some(Utc::now().trunc_subsecs(3)),
+            uniqueness: Some(format!("z{}", bs58::encode(bytes).into_string())),
+        }
+    }
+}
+
+/// Dates are sent as JavaScript's `Date.prototype.toISOString` formats them, always with milliseconds and a `Z` suffix.
+mod js_date {
+    use chrono::{DateTime, SecondsFormat, Utc};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        date: &Option<DateTime<Utc>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match date {
+            Some(date) => {
+                serializer.serialize_str(&date.to_rfc3339_opts(SecondsFormat::Millis, true))
+            }
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<DateTime<Utc>>, D::Error> {
+        Option::<DateTime<Utc>>::deserialize(deserializer)
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
-#[serde(rename_all_fields = "camelCase")]
+#[serde(rename_all = "camelCase", rename_all_fields = "camelCase")]
 #[serde(tag = "type")]
 pub enum Ruleset {
     UnsafeAllowAll,
-    Group { initial_admin: RawAccountID },
-    OwnedByGroup { group: RawCoID },
+    /// A group, created by an account or, for the group of an account itself, by the agent the account was created with.
+    Group {
+        initial_admin: SessionOwner,
+    },
+    OwnedByGroup {
+        group: RawCoID,
+    },
 }
 
 pub enum SyncRole {
@@ -36,6 +90,61 @@ pub enum SyncRole {
     Storage,
 }
 
-pub trait RawCoValue {}
+/// A kind of [`CoValue`], identified by the `type` in its header.
+pub trait RawCoValue {
+    /// The `type` in the headers of [`CoValue`]s of this kind, eg. `comap`.
+    const HEADER_TYPE: &'static str;
+    /// The `type` in the `meta` of the headers of [`CoValue`]s of this kind, if they are a specialisation of another kind, eg. `account`.
+    const HEADER_META_TYPE: Option<&'static str> = None;
+}
+
+/// The error returned when a [`CoValue`] is loaded as a kind its header does not match.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeaderMismatch {
+    pub id: RawCoID,
+    pub expected_type: &'static str,
+    pub expected_meta_type: Option<&'static str>,
+    pub actual_type: String,
+    pub actual_meta_type: Option<String>,
+}
+
+impl Display for HeaderMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} has header type `{}` (meta type {:?}), expected `{}` (meta type {:?})",
+            self.id,
+            self.actual_type,
+            self.actual_meta_type,
+            self.expected_type,
+            self.expected_meta_type
+        )
+    }
+}
+
+impl std::error::Error for HeaderMismatch {}
 
 pub const MAX_RECOMMENDED_TX_SIZE: usize = 100 * 1024;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_random_uniqueness_is_base58_of_12_bytes() {
+        let before = Utc::now().trunc_subsecs(3);
+        let uniqueness = CoValueUniqueness::new_random();
+        let created_at = uniqueness.created_at.unwrap();
+        assert!(before <= created_at && created_at <= Utc::now());
+        assert_eq!(created_at, created_at.trunc_subsecs(3));
+        let string = uniqueness.uniqueness.as_deref().unwrap();
+        let bytes = bs58::decode(string.strip_prefix('z').unwrap())
+            .into_vec()
+            .unwrap();
+        assert_eq!(bytes.len(), 12);
+        assert_ne!(
+            CoValueUniqueness::new_random().uniqueness,
+            uniqueness.uniqueness
+        );
+    }
+}
@@ -1,6 +1,8 @@
-use super::common::{CoValueUniqueness, Ruleset};
+use super::common::{CoValueUniqueness, HeaderMismatch, RawCoValue, Ruleset};
 use crate::{
-    covalue::covaluepriority::CoValuePriority, crypto::short_hash::ShortHash, id::rawcoid::RawCoID,
+    covalue::covaluepriority::CoValuePriority,
+    crypto::provider::{CryptoProvider, NativeCrypto},
+    id::rawcoid::RawCoID,
 };
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
@@ -10,7 +12,6 @@ use serde::{Deserialize, Serialize};
 pub struct CoValueHeader {
     #[serde(rename = "type")]
     type_: String,
-    #[serde(flatten)]
     ruleset: Ruleset,
     pub(crate) meta: Option<serde_json::Value>,
     #[serde(flatten)]
@@ -18,8 +19,76 @@ pub struct CoValueHeader {
 }
 
 impl CoValueHeader {
+    /// Creates a header for a new [`CoValue`], made unique with a random value and the current time.
+    ///
+    /// # Arguments
+    ///
+    /// * `type_` - The type of the [`CoValue`], eg. `comap`.
+    ///
+    /// * `ruleset` - The rules deciding who may make changes to the [`CoValue`].
+    ///
+    /// * `meta` - Additional information about the [`CoValue`].
+    pub fn new(
+        type_: impl Into<String>,
+        ruleset: Ruleset,
+        meta: Option<serde_json::Value>,
+    ) -> Self {
+        Self {
+            type_: type_.into(),
+            ruleset,
+            meta,
+            uniqueness: CoValueUniqueness::new_random(),
+        }
+    }
+
+    /// The type of the [`CoValue`], eg. `comap`.
+    pub fn type_(&self) -> &str {
+        &self.type_
+    }
+
+    pub fn ruleset(&self) -> &Ruleset {
+        &self.ruleset
+    }
+
+    pub fn meta(&self) -> Option<&serde_json::Value> {
+        self.meta.as_ref()
+    }
+
+    /// The `type` in the header's `meta`, if any, eg. `account`.
+    pub fn meta_type(&self) -> Option<&str> {
+        self.meta.as_ref()?.get("type")?.as_str()
+    }
+
+    /// Checks that the header is that of a [`CoValue`] of the given kind.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the [`CoValue`], reported if the header does not match.
+    pub fn check_type<T: RawCoValue>(
+        &self,
+        id: &RawCoID,
+    ) -> std::result::Result<(), HeaderMismatch> {
+        match self.type_ == T::HEADER_TYPE
+            && T::HEADER_META_TYPE.is_none_or(|x| self.meta_type() == Some(x))
+        {
+            true => Ok(()),
+            false => Err(HeaderMismatch {
+                id: id.clone(),
+                expected_type: T::HEADER_TYPE,
+                expected_meta_type: T::HEADER_META_TYPE,
+                actual_type: self.type_.clone(),
+                actual_meta_type: self.meta_type().map(str::to_owned),
+            }),
+        }
+    }
+
     pub fn id(&self) -> Result<RawCoID> {
-        Ok(RawCoID::from(ShortHash::new(serde_json::to_value(self)?)))
+        self.id_with::<NativeCrypto>()
+    }
+
+    /// The ID of a [`CoValue`] with this header, hashed using the given [`CryptoProvider`].
+    pub fn id_with<C: CryptoProvider>(&self) -> Result<RawCoID> {
+        Ok(RawCoID::from(C::short_hash(&serde_json::to_value(self)?)))
     }
 }
 
@@ -28,12 +97,11 @@ impl From<&CoValueHeader> for CoValuePriority {
         let header_meta_type = header
             .meta
             .clone()
-            .map(|x| {
+            .and_then(|x| {
                 x.get("type")
                     .map(|y| y.clone().as_str().map(|z| z.to_string()))
             })
             .flatten()
-            .flatten()
             .unwrap_or_default();
         match (
             header.type_.as_str(),
@@ -80,3 +148,72 @@ impl From<&bool> for CoValuePriority {
         CoValuePriority::Medium
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::id::session_id::SessionOwner;
+
+    /// Headers in the form cojson writes them: an account, the group it created, and a map owned by that group.
+    const ACCOUNT_HEADER: &str = r#"{"type":"comap","ruleset":{"type":"group","initialAdmin":"sealer_zAx5mFGETVoUAuAnKe4RewUGKFL7y2vz7TvYJaWxDMX8u/signer_z6Wz83QvwgG9Sf5EzbtJaAXrXnS1KAEDzMeZCLtthocTx"},"meta":{"type":"account"},"createdAt":null,"uniqueness":null}"#;
+    const GROUP_HEADER: &str = r#"{"type":"comap","ruleset":{"type":"group","initialAdmin":"co_zaSjB3WBb3m3JVewfu7pd2S4J69"},"meta":null,"createdAt":"2025-03-04T10:15:30.123Z","uniqueness":"z3fP7wQk9Lmn2TbXyR5c"}"#;
+    const MAP_HEADER: &str = r#"{"type":"comap","ruleset":{"type":"ownedByGroup","group":"co_zH4PEVimz8LZzpnuR9VFwPkJG2T"},"meta":null,"createdAt":"2025-03-04T10:16:02.481Z","uniqueness":"zA8vXn2KqL4pWeR7tY1m"}"#;
+
+    fn round_trip(json: &str) -> CoValueHeader {
+        let header: CoValueHeader = serde_json::from_str(json).unwrap();
+        assert_eq!(serde_json::to_string(&header).unwrap(), json);
+        header
+    }
+
+    #[test]
+    fn round_trips_account_header() {
+        let header = round_trip(ACCOUNT_HEADER);
+        assert_eq!(header.meta_type(), Some("account"));
+        assert!(matches!(
+            header.ruleset(),
+            Ruleset::Group {
+                initial_admin: SessionOwner::Agent(_)
+            }
+        ));
+        assert_eq!(
+            header.id().unwrap().to_string(),
+            "co_zaSjB3WBb3m3JVewfu7pd2S4J69"
+        );
+    }
+
+    #[test]
+    fn round_trips_group_header() {
+        let header = round_trip(GROUP_HEADER);
+        assert!(matches!(
+            header.ruleset(),
+            Ruleset::Group {
+                initial_admin: SessionOwner::Account(_)
+            }
+        ));
+        assert_eq!(
+            header.id().unwrap().to_string(),
+            "co_zH4PEVimz8LZzpnuR9VFwPkJG2T"
+        );
+    }
+
+    #[test]
+    fn round_trips_owned_header() {
+        let header = round_trip(MAP_HEADER);
+        assert_eq!(header.type_(), "comap");
+        assert_eq!(header.meta(), None);
+        assert_eq!(
+            header.id().unwrap().to_string(),
+            "co_z9DPTVJSEDToSX14GgauM9MMQaM"
+        );
+    }
+
+    #[test]
+    fn new_header_is_unique() {
+        let header = CoValueHeader::new("colist", Ruleset::UnsafeAllowAll, None);
+        let json = serde_json::to_value(&header).unwrap();
+        assert!(json["createdAt"].as_str().unwrap().ends_with('Z'));
+        assert!(json["uniqueness"].as_str().unwrap().starts_with('z'));
+        let other = CoValueHeader::new("colist", Ruleset::UnsafeAllowAll, None);
+        assert_ne!(other.id().unwrap(), header.id().unwrap());
+    }
+}
@@ -1,5 +1,8 @@
+pub mod colist;
+pub mod comap;
 pub mod common;
 pub mod covaluecore;
 pub mod covaluepriority;
 pub mod header;
 pub mod session;
+pub mod signature_cache;
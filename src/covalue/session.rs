@@ -1,8 +1,15 @@
-use crate::covalue::common::MAX_RECOMMENDED_TX_SIZE;
+use crate::covalue::common::{MAX_RECOMMENDED_TX_SIZE, RawCoValue, Ruleset};
 use crate::covalue::covaluepriority::CoValuePriority;
-use crate::crypto::sign::Signature;
+use crate::covalue::signature_cache::SignatureCache;
+use crate::crypto::encrypt::{KeySecret, NonceMaterial};
+use crate::crypto::provider::{CryptoProvider, NativeCrypto};
+use crate::crypto::sign::{Signature, SignerSecret};
+use crate::crypto::stable_stringify::stable_stringify;
+use crate::id::common::{CoID, TransactionID};
+use crate::id::key_id::KeyID;
 use crate::id::session_id::SessionID;
 use crate::id::signer_id::SignerID;
+use crate::permission::group::GroupRoles;
 use crate::sync::common::CoValueKnownState;
 use crate::sync::common::SessionNewContent;
 use crate::sync::common::SyncMessage;
@@ -11,13 +18,14 @@ use crate::{
     crypto::{hash::Hash, streaming_hash::StreamingHash},
     id::rawcoid::RawCoID,
 };
+use chrono::Utc;
 use dashmap::DashMap;
-use dashmap::DashSet;
 use rayon::iter::IntoParallelIterator;
 use rayon::iter::IntoParallelRefIterator;
 use rayon::iter::ParallelIterator;
 use rayon::slice::ParallelSliceMut;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::Arc;
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -38,63 +46,346 @@ impl From<TransactionType> for TransactionPrivacy {
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
-#[serde(rename_all_fields = "camelCase")]
+#[serde(rename_all = "lowercase", rename_all_fields = "camelCase")]
 #[serde(tag = "privacy")]
 pub enum TransactionType {
     /// Transaction is encrypted.
     Private {
         /// ID of the key used for encryption.
-        key_used: Vec<u8>,
+        key_used: KeyID,
+        #[serde(with = "encrypted_changes")]
         encrypted_changes: Vec<u8>,
     },
     /// Transaction is not encrypted.
-    Trusting { changes: Vec<u8> },
+    Trusting {
+        /// The changes made in the transaction, as a JSON string.
+        changes: String,
+    },
+}
+
+/// Encrypted changes are sent as `encrypted_U` followed by the URL-safe Base64 encoding of the ciphertext.
+mod encrypted_changes {
+    use base64::{
+        Engine,
+        alphabet::URL_SAFE,
+        engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig},
+    };
+    use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+    const PREFIX: &str = "encrypted_U";
+    const ENGINE: GeneralPurpose = GeneralPurpose::new(
+        &URL_SAFE,
+        GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+    );
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("{PREFIX}{}", ENGINE.encode(bytes)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.strip_prefix(PREFIX)
+            .and_then(|x| ENGINE.decode(x).ok())
+            .ok_or(D::Error::custom(
+                "String not valid encrypted changes; encrypted changes begin with `encrypted_U` followed by the URL-safe Base64-encoded ciphertext",
+            ))
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
-#[serde(rename_all = "camelCase")]
+#[serde(into = "StoredTransaction", from = "StoredTransaction")]
 pub struct Transaction {
     /// Timestamp of the transaction.
     made_at: u64,
-    #[serde(flatten)]
     type_: TransactionType,
 }
 
+/// A [`Transaction`] as it is sent, with its privacy before its timestamp as cojson writes it.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "lowercase", rename_all_fields = "camelCase")]
+#[serde(tag = "privacy")]
+enum StoredTransaction {
+    Private {
+        made_at: u64,
+        key_used: KeyID,
+        #[serde(with = "encrypted_changes")]
+        encrypted_changes: Vec<u8>,
+    },
+    Trusting {
+        made_at: u64,
+        changes: String,
+    },
+}
+
+impl From<Transaction> for StoredTransaction {
+    fn from(transaction: Transaction) -> Self {
+        let made_at = transaction.made_at;
+        match transaction.type_ {
+            TransactionType::Private {
+                key_used,
+                encrypted_changes,
+            } => StoredTransaction::Private {
+                made_at,
+                key_used,
+                encrypted_changes,
+            },
+            TransactionType::Trusting { changes } => {
+                StoredTransaction::Trusting { made_at, changes }
+            }
+        }
+    }
+}
+
+impl From<StoredTransaction> for Transaction {
+    fn from(stored: StoredTransaction) -> Self {
+        match stored {
+            StoredTransaction::Private {
+                made_at,
+                key_used,
+                encrypted_changes,
+            } => Self {
+                made_at,
+                type_: TransactionType::Private {
+                    key_used,
+                    encrypted_changes,
+                },
+            },
+            StoredTransaction::Trusting { made_at, changes } => Self {
+                made_at,
+                type_: TransactionType::Trusting { changes },
+            },
+        }
+    }
+}
+
+impl Transaction {
+    pub fn new_trusting(made_at: u64, changes: &[serde_json::Value]) -> anyhow::Result<Self> {
+        Ok(Self {
+            made_at,
+            type_: TransactionType::Trusting {
+                changes: stable_stringify(&serde_json::to_value(changes)?),
+            },
+        })
+    }
+
+    /// Creates a transaction whose changes are encrypted with the given key.
+    ///
+    /// # Arguments
+    ///
+    /// * `made_at` - Timestamp of the transaction.
+    ///
+    /// * `changes` - The changes made in the transaction.
+    ///
+    /// * `key_id` - ID of the key used for encryption.
+    ///
+    /// * `key_secret` - The key used for encryption.
+    ///
+    /// * `nonce_material` - The ID of the [`CoValue`] and transaction the changes belong to.
+    pub fn new_private<C: CryptoProvider>(
+        made_at: u64,
+        changes: &[serde_json::Value],
+        key_id: &KeyID,
+        key_secret: &KeySecret,
+        nonce_material: &NonceMaterial,
+    ) -> anyhow::Result<Self> {
+        Ok(Self {
+            made_at,
+            type_: TransactionType::Private {
+                key_used: *key_id,
+                encrypted_changes: C::encrypt(
+                    key_secret,
+                    &serde_json::to_value(changes)?,
+                    nonce_material,
+                )?,
+            },
+        })
+    }
+
+    pub fn made_at(&self) -> u64 {
+        self.made_at
+    }
+
+    /// ID of the key used for encryption, if the transaction is encrypted.
+    pub fn key_used(&self) -> Option<&KeyID> {
+        match &self.type_ {
+            TransactionType::Private { key_used, .. } => Some(key_used),
+            TransactionType::Trusting { .. } => None,
+        }
+    }
+
+    /// Reads the changes made in the transaction, decrypting them if necessary.
+    ///
+    /// # Arguments
+    ///
+    /// * `key_secret` - The key used for encryption; ignored if the transaction is not encrypted.
+    ///
+    /// * `nonce_material` - The ID of the [`CoValue`] and transaction the changes belong to.
+    pub fn changes<C: CryptoProvider>(
+        &self,
+        key_secret: Option<&KeySecret>,
+        nonce_material: &NonceMaterial,
+    ) -> anyhow::Result<Vec<serde_json::Value>> {
+        match (&self.type_, key_secret) {
+            (TransactionType::Trusting { changes }, _) => Ok(serde_json::from_str(changes)?),
+            (
+                TransactionType::Private {
+                    key_used: _,
+                    encrypted_changes,
+                },
+                Some(key_secret),
+            ) => Ok(serde_json::from_value(C::decrypt(
+                key_secret,
+                encrypted_changes,
+                nonce_material,
+            )?)?),
+            (TransactionType::Private { key_used, .. }, None) => Err(anyhow::anyhow!(
+                "Transaction is encrypted with key {key_used}, but no key was given"
+            )),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
 #[serde(rename_all = "camelCase")]
-pub struct SessionLog {
+#[serde(bound = "")]
+#[serde(try_from = "StoredSessionLog")]
+pub struct SessionLog<C: CryptoProvider = NativeCrypto> {
     pub(crate) transactions: Vec<Transaction>,
     /// Latest rolling hash of the session's transactions.
     last_hash: Option<Hash>,
-    streaming_hash: StreamingHash,
+    /// State of the rolling hash, rebuilt from the transactions when the session is loaded.
+    #[serde(skip)]
+    streaming_hash: StreamingHash<C>,
     /// List of signatures after each transaction.
     signature_after: Vec<Option<Signature>>,
     /// Latest signed hash of the session's transactions.
     last_signature: Signature,
 }
 
+/// A [`SessionLog`] as it is stored, without the state of its rolling hash.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StoredSessionLog {
+    transactions: Vec<Transaction>,
+    last_hash: Option<Hash>,
+    signature_after: Vec<Option<Signature>>,
+    last_signature: Signature,
+}
+
+impl<C: CryptoProvider> TryFrom<StoredSessionLog> for SessionLog<C> {
+    type Error = anyhow::Error;
+
+    fn try_from(stored: StoredSessionLog) -> anyhow::Result<Self> {
+        let mut streaming_hash = StreamingHash::<C>::default();
+        for transaction in &stored.transactions {
+            streaming_hash.update(serde_json::to_value(transaction)?);
+        }
+        let rebuilt_hash = (!stored.transactions.is_empty()).then(|| streaming_hash.digest());
+        if rebuilt_hash != stored.last_hash {
+            return Err(anyhow::anyhow!(
+                "Session's transactions do not match its last hash; the session may be corrupted"
+            ));
+        }
+        Ok(Self {
+            transactions: stored.transactions,
+            last_hash: stored.last_hash,
+            streaming_hash,
+            signature_after: stored.signature_after,
+            last_signature: stored.last_signature,
+        })
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
-pub struct ExpectedNewHashAfter {
+#[serde(bound = "")]
+pub struct ExpectedNewHashAfter<C: CryptoProvider = NativeCrypto> {
     expected_new_hash: Hash,
-    new_streaming_hash: Arc<StreamingHash>,
+    new_streaming_hash: Arc<StreamingHash<C>>,
+}
+
+/// New content for a session, with its expected hash computed but its signature not yet verified.
+#[derive(Debug)]
+struct PreparedNewContent<C: CryptoProvider> {
+    session_id: SessionID,
+    signer_id: SignerID,
+    /// The message the signature of the new content is expected to sign.
+    signed_hash: serde_json::Value,
+    content: SessionNewContent,
+    /// Number of transactions at the start of the content already in the session.
+    already_known: usize,
+    expected_new_hash: Hash,
+    new_streaming_hash: Arc<StreamingHash<C>>,
+}
+
+impl<C: CryptoProvider> PreparedNewContent<C> {
+    /// Number of transactions the session will have once the content is added.
+    fn new_tx_count(&self) -> usize {
+        self.content.after + self.content.new_transactions.len()
+    }
+}
+
+/// A transaction whose changes have been read, identified by where it was made.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidTransaction {
+    pub tx_id: TransactionID,
+    /// Timestamp of the transaction.
+    pub made_at: u64,
+    pub changes: Vec<serde_json::Value>,
+}
+
+/// The session a local agent makes new transactions in, with the secrets to sign and, optionally, encrypt them.
+#[derive(Debug, Clone)]
+pub struct SessionWriter {
+    session_id: SessionID,
+    signer_secret: SignerSecret,
+    /// The key new transactions are encrypted with, if they are private.
+    key: Option<(KeyID, KeySecret)>,
+}
+
+impl SessionWriter {
+    /// Creates a writer making trusting transactions in the given session.
+    pub fn new(session_id: SessionID, signer_secret: SignerSecret) -> Self {
+        Self {
+            session_id,
+            signer_secret,
+            key: None,
+        }
+    }
+
+    /// Encrypts new transactions with the given key, making them private.
+    pub fn with_key(mut self, key_id: KeyID, key_secret: KeySecret) -> Self {
+        self.key = Some((key_id, key_secret));
+        self
+    }
+
+    pub fn session_id(&self) -> &SessionID {
+        &self.session_id
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
-pub struct VerifiedState {
+#[serde(bound = "")]
+pub struct VerifiedState<C: CryptoProvider = NativeCrypto> {
     id: RawCoID,
     header: CoValueHeader,
-    sessions: DashMap<SessionID, SessionLog>,
+    sessions: DashMap<SessionID, SessionLog<C>>,
     cached_known_state: Option<CoValueKnownState>,
     cached_new_content_since_empty: Option<Vec<SyncMessage>>,
+    /// Checkpoints whose signatures are already verified; may be shared between states.
+    #[serde(skip)]
+    signature_cache: Arc<SignatureCache>,
+    /// The group owning this [`CoValue`], if its ruleset is [`Ruleset::OwnedByGroup`] and the group is loaded.
+    #[serde(skip)]
+    group: Option<Arc<VerifiedState<C>>>,
 }
 
-impl VerifiedState {
+impl<C: CryptoProvider> VerifiedState<C> {
     pub fn new(
         id: &RawCoID,
         header: &CoValueHeader,
-        sessions: &DashMap<SessionID, SessionLog>,
+        sessions: &DashMap<SessionID, SessionLog<C>>,
     ) -> Self {
         Self {
             id: id.clone(),
@@ -102,17 +393,68 @@ impl VerifiedState {
             sessions: sessions.clone(),
             cached_known_state: None,
             cached_new_content_since_empty: None,
+            signature_cache: Arc::default(),
+            group: None,
         }
     }
 
+    pub fn id(&self) -> &RawCoID {
+        &self.id
+    }
+
+    pub fn header(&self) -> &CoValueHeader {
+        &self.header
+    }
+
+    /// Loads the state as a [`CoValue`] of the kind the given ID refers to, checking its header.
+    ///
+    /// # Returns
+    ///
+    /// A [`HeaderMismatch`](crate::covalue::common::HeaderMismatch) error if the [`CoValue`] is of another kind.
+    pub fn load_as<T: RawCoValue>(&self, id: &CoID<T>) -> anyhow::Result<&Self> {
+        id.check_header_with::<C>(&self.header)?;
+        Ok(self)
+    }
+
+    /// Uses the given cache of verified signatures, so that content verified by other states sharing it is not verified again.
+    pub fn with_signature_cache(mut self, signature_cache: Arc<SignatureCache>) -> Self {
+        self.signature_cache = signature_cache;
+        self
+    }
+
+    pub fn signature_cache(&self) -> &Arc<SignatureCache> {
+        &self.signature_cache
+    }
+
+    /// Checks transactions against the roles in the given group, which should be the group owning this [`CoValue`].
+    ///
+    /// Until its group is given, the transactions of a [`CoValue`] owned by a group are not checked against its roles.
+    pub fn with_group(mut self, group: Arc<VerifiedState<C>>) -> Self {
+        self.group = Some(group);
+        self
+    }
+
+    /// The roles of the members of this group as they were at a given time.
+    ///
+    /// # Returns
+    ///
+    /// [`None`] if this [`CoValue`] is not a group.
+    pub fn group_roles(&self, time: u64) -> Option<GroupRoles> {
+        let Ruleset::Group { initial_admin } = self.header.ruleset() else {
+            return None;
+        };
+        let transactions = self.readable_transactions(&HashMap::new(), &HashMap::new(), time);
+        Some(GroupRoles::from_transactions(initial_admin, transactions).0)
+    }
+
     pub fn expected_new_hash_after(
         &self,
         session_id: &SessionID,
-        new_transactions: &Vec<Transaction>,
-    ) -> anyhow::Result<ExpectedNewHashAfter> {
+        new_transactions: &[Transaction],
+    ) -> anyhow::Result<ExpectedNewHashAfter<C>> {
         let mut streaming_hash = self
             .sessions
-            .get(&session_id)
+            .get(session_id)
             .map(|x| x.streaming_hash.clone())
             .unwrap_or_default();
         for transaction in new_transactions {
@@ -127,30 +469,30 @@ impl VerifiedState {
     fn do_add_transactions(
         &mut self,
         session_id: &SessionID,
-        new_transactions: &Vec<Transaction>,
+        new_transactions: &[Transaction],
         new_signature: &Signature,
         expected_new_hash: &Hash,
-        new_streaming_hash: &StreamingHash,
+        new_streaming_hash: &StreamingHash<C>,
     ) {
         let mut transactions: Vec<_> = self
             .sessions
             .get(session_id)
             .map(|x| x.transactions.clone())
             .unwrap_or_default();
-        transactions.append(&mut new_transactions.clone());
+        transactions.extend_from_slice(new_transactions);
         let mut signature_after = self
             .sessions
             .get(session_id)
             .map(|x| x.signature_after.clone())
             .unwrap_or_default();
-        let last_inbetween_signature_idx = signature_after
+        signature_after.resize(transactions.len(), None);
+        let first_tx_since_last_inbetween_signature = signature_after
             .iter()
-            .enumerate()
-            .max_by_key(|x| x.0)
-            .map(|x| x.0)
+            .rposition(Option::is_some)
+            .map(|x| x + 1)
             .unwrap_or_default();
         let size_of_txs_since_last_inbetween_signature = transactions.as_slice()
-            [last_inbetween_signature_idx + 1..]
+            [first_tx_since_last_inbetween_signature..]
             .iter()
             .fold(0, |sum, tx| {
                 sum + match tx.type_.clone() {
@@ -162,15 +504,15 @@ impl VerifiedState {
                 }
             });
         if size_of_txs_since_last_inbetween_signature > MAX_RECOMMENDED_TX_SIZE {
-            signature_after[transactions.len() - 1] = Some(new_signature.clone());
+            signature_after[transactions.len() - 1] = Some(*new_signature);
         }
         self.sessions.insert(
             session_id.clone(),
             SessionLog {
-                transactions: transactions,
+                transactions,
                 last_hash: Some(*expected_new_hash),
                 streaming_hash: new_streaming_hash.clone(),
-                signature_after: signature_after,
+                signature_after,
                 last_signature: *new_signature,
             },
         );
@@ -178,6 +520,221 @@ impl VerifiedState {
         self.cached_known_state = None;
     }
 
+    /// Reads the changes made in a transaction of this [`CoValue`], decrypting them if necessary.
+    pub fn transaction_changes(
+        &self,
+        session_id: &SessionID,
+        tx_index: usize,
+        key_secret: Option<&KeySecret>,
+    ) -> anyhow::Result<Vec<serde_json::Value>> {
+        let session = self.sessions.get(session_id).ok_or(anyhow::anyhow!(
+            "Session {session_id} not found in {}",
+            self.id
+        ))?;
+        let transaction = session.transactions.get(tx_index).ok_or(anyhow::anyhow!(
+            "Transaction {tx_index} not found in session {session_id} of {}",
+            self.id
+        ))?;
+        transaction.changes::<C>(
+            key_secret,
+            &NonceMaterial::new(
+                self.id.clone(),
+                TransactionID::new(session_id.clone(), tx_index),
+            ),
+        )
+    }
+
+    /// The transactions of this [`CoValue`] that can be read, with their changes decoded, ordered by when they were made.
+    ///
+    /// Transactions made at the same time are ordered by their [`TransactionID`].
+    /// Encrypted transactions whose key is not given, transactions whose changes cannot be decoded, and transactions
+    /// made by someone without permission to make them at the time are skipped.
+    /// Permissions are checked where the roles are known: a group checks changes to itself against its own roles, and a
+    /// [`CoValue`] owned by a group checks its transactions against the roles of the group given with [`VerifiedState::with_group`].
+    ///
+    /// # Arguments
+    ///
+    /// * `read_keys` - The keys encrypted transactions may be decrypted with.
+    pub fn valid_transactions(
+        &self,
+        read_keys: &HashMap<KeyID, KeySecret>,
+    ) -> Vec<ValidTransaction> {
+        self.valid_transactions_until(read_keys, u64::MAX)
+    }
+
+    /// The transactions of this [`CoValue`] that can be read and were made at or before a given time, ordered by when they were made.
+    ///
+    /// Transactions made later are left out before any are decoded, so nothing made after that time affects the result.
+    /// Permissions are checked as of that time too: each transaction is checked against the roles its author had when it was
+    /// made, read from only the transactions of the group made by then.
+    ///
+    /// # Arguments
+    ///
+    /// * `read_keys` - The keys encrypted transactions may be decrypted with.
+    ///
+    /// * `time` - The time to read the [`CoValue`] as of, in milliseconds since the Unix epoch.
+    pub fn valid_transactions_until(
+        &self,
+        read_keys: &HashMap<KeyID, KeySecret>,
+        time: u64,
+    ) -> Vec<ValidTransaction> {
+        self.valid_transactions_since(read_keys, &HashMap::new(), time)
+    }
+
+    /// The transactions of this [`CoValue`] that can be read, made at or before a given time, and not among the first transactions of each session already read.
+    ///
+    /// Sessions only grow, so this gives the transactions added since an earlier read without decoding the others again.
+    ///
+    /// # Arguments
+    ///
+    /// * `read_keys` - The keys encrypted transactions may be decrypted with.
+    ///
+    /// * `since` - The number of transactions already read from each session.
+    ///
+    /// * `time` - The time to read the [`CoValue`] as of, in milliseconds since the Unix epoch.
+    pub fn valid_transactions_since(
+        &self,
+        read_keys: &HashMap<KeyID, KeySecret>,
+        since: &HashMap<SessionID, usize>,
+        time: u64,
+    ) -> Vec<ValidTransaction> {
+        let after_since = |transaction: &ValidTransaction| {
+            transaction.tx_id.tx_index()
+                >= since
+                    .get(transaction.tx_id.session_id())
+                    .copied()
+                    .unwrap_or_default()
+        };
+        match self.header.ruleset() {
+            Ruleset::UnsafeAllowAll => self.readable_transactions(read_keys, since, time),
+            // Whether a transaction of a group is valid depends on the group's earlier transactions, so all of them are read.
+            Ruleset::Group { initial_admin } => GroupRoles::from_transactions(
+                initial_admin,
+                self.readable_transactions(read_keys, &HashMap::new(), time),
+            )
+            .1
+            .into_iter()
+            .filter(after_since)
+            .collect(),
+            Ruleset::OwnedByGroup { group } => {
+                let Some(roles) = self
+                    .group
+                    .as_ref()
+                    .filter(|x| &x.id == group)
+                    .and_then(|x| x.group_roles(time))
+                else {
+                    return self.readable_transactions(read_keys, since, time);
+                };
+                self.readable_transactions(read_keys, since, time)
+                    .into_iter()
+                    .filter(|x| roles.can_write_at(x.tx_id.session_id().owner(), x.made_at))
+                    .collect()
+            }
+        }
+    }
+
+    /// The transactions that can be read, made at or before a given time, and not already read, regardless of permissions.
+    fn readable_transactions(
+        &self,
+        read_keys: &HashMap<KeyID, KeySecret>,
+        since: &HashMap<SessionID, usize>,
+        time: u64,
+    ) -> Vec<ValidTransaction> {
+        let mut transactions: Vec<_> = self
+            .sessions
+            .par_iter()
+            .flat_map_iter(|session| {
+                let session_id = session.key();
+                session
+                    .value()
+                    .transactions
+                    .iter()
+                    .enumerate()
+                    .skip(since.get(session_id).copied().unwrap_or_default())
+                    .filter(|(_, transaction)| transaction.made_at() <= time)
+                    .filter_map(|(tx_index, transaction)| {
+                        let tx_id = TransactionID::new(session_id.clone(), tx_index);
+                        let changes = transaction
+                            .changes::<C>(
+                                transaction.key_used().and_then(|x| read_keys.get(x)),
+                                &NonceMaterial::new(self.id.clone(), tx_id.clone()),
+                            )
+                            .ok()?;
+                        Some(ValidTransaction {
+                            tx_id,
+                            made_at: transaction.made_at(),
+                            changes,
+                        })
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        transactions
+            .par_sort_unstable_by(|a, b| Ord::cmp(&(a.made_at, &a.tx_id), &(b.made_at, &b.tx_id)));
+        transactions
+    }
+
+    /// Makes a new transaction in the writer's session, signing it and adding it to this [`CoValue`].
+    ///
+    /// The transaction is encrypted if the writer has a key, and is otherwise trusting.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - The session to make the transaction in, and the secrets to sign and encrypt it with.
+    ///
+    /// * `changes` - The changes made in the transaction.
+    pub fn make_transaction(
+        &mut self,
+        writer: &SessionWriter,
+        changes: &[serde_json::Value],
+    ) -> anyhow::Result<ValidTransaction> {
+        let made_at = u64::try_from(Utc::now().timestamp_millis())?;
+        self.make_transaction_at(writer, made_at, changes)
+    }
+
+    /// Makes a new transaction as [`VerifiedState::make_transaction`] does, but timestamped with the given time rather than now.
+    pub(crate) fn make_transaction_at(
+        &mut self,
+        writer: &SessionWriter,
+        made_at: u64,
+        changes: &[serde_json::Value],
+    ) -> anyhow::Result<ValidTransaction> {
+        let tx_index = self
+            .sessions
+            .get(&writer.session_id)
+            .map(|x| x.transactions.len())
+            .unwrap_or_default();
+        let tx_id = TransactionID::new(writer.session_id.clone(), tx_index);
+        let transaction = match &writer.key {
+            Some((key_id, key_secret)) => Transaction::new_private::<C>(
+                made_at,
+                changes,
+                key_id,
+                key_secret,
+                &NonceMaterial::new(self.id.clone(), tx_id.clone()),
+            )?,
+            None => Transaction::new_trusting(made_at, changes)?,
+        };
+        let new_transactions = [transaction];
+        let ExpectedNewHashAfter {
+            expected_new_hash,
+            new_streaming_hash,
+        } = self.expected_new_hash_after(&writer.session_id, &new_transactions)?;
+        let signature = C::sign(&writer.signer_secret, &expected_new_hash.to_string().into());
+        self.do_add_transactions(
+            &writer.session_id,
+            &new_transactions,
+            &signature,
+            &expected_new_hash,
+            &new_streaming_hash,
+        );
+        Ok(ValidTransaction {
+            tx_id,
+            made_at,
+            changes: changes.to_vec(),
+        })
+    }
+
     pub fn known_state(&mut self) -> CoValueKnownState {
         match &self.cached_known_state {
             Some(cached_known_state) => cached_known_state.clone(),
@@ -201,41 +758,35 @@ impl VerifiedState {
         CoValueKnownState {
             id: self.id.clone(),
             header: true,
-            sessions: sessions,
+            sessions,
         }
     }
 
+    /// The index of the first transaction of a session with a signature after it, that was neither known nor sent yet.
     pub fn get_known_signature_idx(
-        log: &SessionLog,
+        log: &SessionLog<C>,
         known_state_for_session_id: Option<&usize>,
         sent_state_for_session_id: Option<&usize>,
     ) -> Option<usize> {
-        let mut signature_after_keys: Vec<_> = log
-            .signature_after
+        let first_new_tx_idx =
+            *sent_state_for_session_id.unwrap_or(known_state_for_session_id.unwrap_or(&0));
+        log.signature_after
             .iter()
             .enumerate()
-            .map(|x| x.0)
-            .collect();
-        signature_after_keys.par_sort_unstable_by(|a, b| a.cmp(&b));
-        signature_after_keys
-            .iter()
-            .find(|idx| {
-                **idx
-                    >= (*sent_state_for_session_id
-                        .unwrap_or(&(known_state_for_session_id.map(|x| *x)).unwrap_or_default()))
-            })
-            .map(|x| *x)
+            .skip(first_new_tx_idx)
+            .find_map(|(idx, signature)| signature.map(|_| idx))
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn try_add_transactions(
         &mut self,
         session_id: &SessionID,
         signer_id: &SignerID,
-        new_transactions: &Vec<Transaction>,
+        new_transactions: &[Transaction],
         given_expected_new_hash: &Option<Hash>,
         new_signature: &Signature,
         skip_verify: &Option<bool>,
-        given_new_streaming_hash: &Option<StreamingHash>,
+        given_new_streaming_hash: &Option<StreamingHash<C>>,
     ) -> anyhow::Result<()> {
         let skip_verify = skip_verify.unwrap_or(false);
         match (
@@ -243,40 +794,220 @@ impl VerifiedState {
             given_new_streaming_hash,
             given_expected_new_hash,
         ) {
-            (true, Some(given_new_streaming_hash), Some(given_expected_new_hash)) => Ok(self
-                .do_add_transactions(
+            (true, Some(given_new_streaming_hash), Some(given_expected_new_hash)) => {
+                self.do_add_transactions(
                     session_id,
                     new_transactions,
                     new_signature,
                     given_expected_new_hash,
                     given_new_streaming_hash,
-                )),
+                );
+                Ok(())
+            }
             _ => {
                 let ExpectedNewHashAfter {
                     expected_new_hash,
                     new_streaming_hash,
                 } = self.expected_new_hash_after(session_id, new_transactions)?;
-                if let Some(given_expected_new_hash) = given_expected_new_hash {
-                    if given_expected_new_hash != &expected_new_hash {
-                        return Err(anyhow::anyhow!(
-                            "Invalid hash for session {} does not match (expected: {given_expected_new_hash}, actual: {expected_new_hash}",
-                            self.id
-                        ));
-                    }
+                if let Some(given_expected_new_hash) = given_expected_new_hash
+                    && given_expected_new_hash != &expected_new_hash
+                {
+                    return Err(anyhow::anyhow!(
+                        "Invalid hash for session {} does not match (expected: {given_expected_new_hash}, actual: {expected_new_hash}",
+                        self.id
+                    ));
+                }
+                let new_tx_count = self
+                    .sessions
+                    .get(session_id)
+                    .map(|x| x.transactions.len())
+                    .unwrap_or_default()
+                    + new_transactions.len();
+                if !self.signature_cache.contains(
+                    session_id,
+                    signer_id,
+                    new_tx_count,
+                    &expected_new_hash,
+                    new_signature,
+                ) {
+                    C::verify(
+                        signer_id,
+                        &expected_new_hash.to_string().into(),
+                        new_signature,
+                    )?;
+                    self.signature_cache.insert(
+                        session_id,
+                        signer_id,
+                        new_tx_count,
+                        &expected_new_hash,
+                        new_signature,
+                    );
                 }
-                signer_id.verify(expected_new_hash.to_string(), new_signature)?;
                 self.do_add_transactions(
                     session_id,
                     new_transactions,
                     new_signature,
                     &expected_new_hash,
-                    &*new_streaming_hash,
+                    &new_streaming_hash,
                 );
                 Ok(())
             }
         }
     }
 
+    /// Adds the content of a [`SyncMessage::NewContentMessage`] for this [`CoValue`], verifying all of its sessions at once.
+    ///
+    /// The expected hashes of all sessions are computed in parallel, and their signatures are verified in a single batch.
+    /// If the batch fails, each session is verified individually, so that the sessions with valid signatures are still added.
+    /// Sessions reaching a checkpoint already in the signature cache are not verified again.
+    ///
+    /// # Arguments
+    ///
+    /// * `new_content` - The content to add.
+    ///
+    /// * `signer_for_session` - Resolves the signer expected to have signed the transactions of a session.
+    ///
+    /// # Returns
+    ///
+    /// An error naming every session whose content could not be added.
+    pub fn try_add_new_content(
+        &mut self,
+        new_content: &SyncMessage,
+        signer_for_session: impl Fn(&SessionID) -> anyhow::Result<SignerID> + Sync,
+    ) -> anyhow::Result<()> {
+        let SyncMessage::NewContentMessage { id, new, .. } = new_content else {
+            return Err(anyhow::anyhow!(
+                "Only new content messages can be added to {}",
+                self.id
+            ));
+        };
+        if id != &self.id {
+            return Err(anyhow::anyhow!(
+                "New content for {id} cannot be added to {}",
+                self.id
+            ));
+        }
+        let sessions: Vec<_> = new
+            .iter()
+            .map(|x| (x.key().clone(), x.value().clone()))
+            .collect();
+        let mut failures = Vec::new();
+        let mut prepared = Vec::new();
+        let prepared_sessions: Vec<_> = sessions
+            .par_iter()
+            .map(|(session_id, content)| {
+                self.prepare_new_content(session_id, content, &signer_for_session)
+                    .map_err(|e| format!("{session_id}: {e}"))
+            })
+            .collect();
+        for x in prepared_sessions {
+            match x {
+                Ok(Some(x)) => prepared.push(x),
+                Ok(None) => (),
+                Err(e) => failures.push(e),
+            }
+        }
+
+        let (cached, prepared): (Vec<_>, Vec<_>) = prepared.into_iter().partition(|x| {
+            self.signature_cache.contains(
+                &x.session_id,
+                &x.signer_id,
+                x.new_tx_count(),
+                &x.expected_new_hash,
+                &x.content.last_signature,
+            )
+        });
+        let batch: Vec<_> = prepared
+            .iter()
+            .map(|x| (&x.signer_id, &x.signed_hash, &x.content.last_signature))
+            .collect();
+        let verified: Vec<_> = match C::verify_batch(&batch) {
+            Ok(()) => prepared.into_iter().map(Ok).collect(),
+            Err(_) => prepared
+                .into_par_iter()
+                .map(
+                    |x| match C::verify(&x.signer_id, &x.signed_hash, &x.content.last_signature) {
+                        Ok(()) => Ok(x),
+                        Err(e) => Err(format!("{}: {e}", x.session_id)),
+                    },
+                )
+                .collect(),
+        };
+
+        for x in cached.into_iter().map(Ok).chain(verified) {
+            match x {
+                Ok(x) => {
+                    self.signature_cache.insert(
+                        &x.session_id,
+                        &x.signer_id,
+                        x.new_tx_count(),
+                        &x.expected_new_hash,
+                        &x.content.last_signature,
+                    );
+                    self.do_add_transactions(
+                        &x.session_id,
+                        &x.content.new_transactions[x.already_known..],
+                        &x.content.last_signature,
+                        &x.expected_new_hash,
+                        &x.new_streaming_hash,
+                    )
+                }
+                Err(e) => failures.push(e),
+            }
+        }
+
+        match failures.is_empty() {
+            true => Ok(()),
+            false => Err(anyhow::anyhow!(
+                "Unable to add new content to {} for {} session(s): {}",
+                self.id,
+                failures.len(),
+                failures.join("; ")
+            )),
+        }
+    }
+
+    /// Computes the hash a session would have after adding new content, without verifying its signature.
+    ///
+    /// # Returns
+    ///
+    /// [`None`] if all of the content is already known.
+    fn prepare_new_content(
+        &self,
+        session_id: &SessionID,
+        content: &SessionNewContent,
+        signer_for_session: &(impl Fn(&SessionID) -> anyhow::Result<SignerID> + Sync),
+    ) -> anyhow::Result<Option<PreparedNewContent<C>>> {
+        let known = self
+            .sessions
+            .get(session_id)
+            .map(|x| x.transactions.len())
+            .unwrap_or_default();
+        if content.after > known {
+            return Err(anyhow::anyhow!(
+                "Content starts after transaction {}, but only {known} transaction(s) are known",
+                content.after
+            ));
+        }
+        let already_known = known - content.after;
+        if already_known >= content.new_transactions.len() {
+            return Ok(None);
+        }
+        let ExpectedNewHashAfter {
+            expected_new_hash,
+            new_streaming_hash,
+        } = self.expected_new_hash_after(session_id, &content.new_transactions[already_known..])?;
+        Ok(Some(PreparedNewContent {
+            session_id: session_id.clone(),
+            signer_id: signer_for_session(session_id)?,
+            signed_hash: expected_new_hash.to_string().into(),
+            content: content.clone(),
+            already_known,
+            expected_new_hash,
+            new_streaming_hash,
+        }))
+    }
+
     pub fn new_content_since(
         &mut self,
         known_state: &Option<CoValueKnownState>,
@@ -285,46 +1016,42 @@ impl VerifiedState {
             .as_ref()
             .map(|x| !x.header && x.sessions.is_empty())
             .unwrap_or(true);
-        match (
+        if let (true, Some(cached_new_content_since_empty)) = (
             is_known_state_empty,
             self.cached_new_content_since_empty.as_ref(),
         ) {
-            (true, Some(cached_new_content_since_empty)) => {
-                return Some(cached_new_content_since_empty.clone());
-            }
-            _ => (),
+            return Some(cached_new_content_since_empty.clone());
         };
 
-        let mut current_piece = SyncMessage::NewContentMessage {
+        let new_piece = |header: Option<CoValueHeader>| SyncMessage::NewContentMessage {
             id: self.id.clone(),
-            header: match known_state.as_ref().map(|x| x.header).unwrap_or(true) {
-                true => None,
-                false => Some(self.header.clone()),
-            },
+            header,
             priority: CoValuePriority::from(&self.header),
             new: DashMap::new(),
         };
-        let mut pieces = vec![current_piece.clone()];
-        let sent_state: DashMap<SessionID, usize> = DashMap::new();
+        let mut pieces = vec![new_piece(
+            match known_state.as_ref().is_some_and(|x| x.header) {
+                true => None,
+                false => Some(self.header.clone()),
+            },
+        )];
+        let mut sent_state: HashMap<SessionID, usize> = HashMap::new();
         let mut piece_size = 0;
-        let mut sessions_to_do_again: Option<DashSet<SessionID>> = None;
-        while sessions_to_do_again.as_ref().is_none_or(|x| !x.is_empty()) {
-            let sessions_to_do = (sessions_to_do_again)
-                .clone()
-                .unwrap_or(self.sessions.par_iter().map(|x| x.key().clone()).collect());
-            for x in sessions_to_do.iter() {
-                let session_id = x.key();
+        // Sessions are sent up to their next signature at a time, so each piece ends at a signed checkpoint.
+        let mut sessions_to_do: Vec<SessionID> =
+            self.sessions.iter().map(|x| x.key().clone()).collect();
+        while !sessions_to_do.is_empty() {
+            let mut sessions_to_do_again = Vec::new();
+            for session_id in sessions_to_do {
                 let log = &(self
                     .sessions
-                    .get(session_id)
+                    .get(&session_id)
                     .map(|x| x.value().clone())
                     .unwrap_or_default());
                 let known_state_for_session_id = known_state
                     .as_ref()
-                    .map(|x| x.sessions.get(session_id).map(|y| y.value().clone()))
-                    .flatten();
-                let sent_state_for_session_id =
-                    sent_state.get(session_id).map(|x| x.value().clone());
+                    .and_then(|x| x.sessions.get(&session_id).map(|y| *y.value()));
+                let sent_state_for_session_id = sent_state.get(&session_id).copied();
                 let next_known_signature_idx = Self::get_known_signature_idx(
                     log,
                     known_state_for_session_id.as_ref(),
@@ -336,115 +1063,49 @@ impl VerifiedState {
                 let after_last_new_tx_idx = next_known_signature_idx
                     .map(|x| x + 1)
                     .unwrap_or(log.transactions.len());
-                let n_new_tx = usize::max(0, after_last_new_tx_idx - first_new_tx_idx);
-
-                match (n_new_tx, &sessions_to_do_again) {
-                    (0, Some(sessions_to_do_again)) => {
-                        sessions_to_do_again.remove(session_id);
-                        continue;
-                    }
-                    _ => (),
-                };
-
+                if after_last_new_tx_idx <= first_new_tx_idx {
+                    continue;
+                }
                 if after_last_new_tx_idx < log.transactions.len() {
-                    match &sessions_to_do_again {
-                        None => {
-                            sessions_to_do_again = Some(DashSet::new());
-                        }
-                        Some(sessions_to_do_again) => {
-                            sessions_to_do_again.insert(session_id.clone());
-                        }
-                    }
+                    sessions_to_do_again.push(session_id.clone());
                 }
 
+                let new_transactions = &log.transactions[first_new_tx_idx..after_last_new_tx_idx];
                 let old_piece_size = piece_size;
-                for tx in log
-                    .transactions
-                    .iter()
-                    .skip(first_new_tx_idx)
-                    .take(n_new_tx)
-                {
-                    piece_size = piece_size
-                        + match &tx.type_ {
-                            TransactionType::Private {
-                                key_used: _,
-                                encrypted_changes,
-                            } => encrypted_changes.len(),
-                            TransactionType::Trusting { changes } => changes.len(),
-                        }
-                }
-
-                if piece_size >= MAX_RECOMMENDED_TX_SIZE {
-                    current_piece = SyncMessage::NewContentMessage {
-                        id: self.id.clone(),
-                        header: None,
-                        priority: self.header.clone().into(),
-                        new: DashMap::new(),
-                    };
-                    pieces.push(current_piece.clone());
-                    piece_size = piece_size - old_piece_size;
-                }
-
-                let mut session_entry = match &current_piece {
-                    SyncMessage::NewContentMessage {
-                        id: _,
-                        header: _,
-                        priority: _,
-                        new,
-                    } => new.get(session_id).map(|x| x.value().clone()),
-                    _ => None,
-                };
-                if let None = session_entry {
-                    session_entry = Some(SessionNewContent {
-                        after: sent_state_for_session_id
-                            .unwrap_or(known_state_for_session_id.unwrap_or(0)),
-                        new_transactions: vec![],
-                        last_signature: Signature::default(),
-                    });
-                    match (&current_piece, &session_entry) {
-                        (
-                            SyncMessage::NewContentMessage {
-                                id: _,
-                                header: _,
-                                priority: _,
-                                new,
-                            },
-                            Some(session_entry),
-                        ) => {
-                            new.insert(session_id.clone(), session_entry.clone());
-                        }
-                        _ => (),
+                for tx in new_transactions {
+                    piece_size += match &tx.type_ {
+                        TransactionType::Private {
+                            key_used: _,
+                            encrypted_changes,
+                        } => encrypted_changes.len(),
+                        TransactionType::Trusting { changes } => changes.len(),
                     }
                 }
 
-                for tx in log
-                    .transactions
-                    .iter()
-                    .skip(first_new_tx_idx)
-                    .take(n_new_tx)
-                {
-                    if let Some(session_entry) = &mut session_entry {
-                        session_entry.new_transactions.push(tx.clone());
-                    }
+                if piece_size >= MAX_RECOMMENDED_TX_SIZE {
+                    pieces.push(new_piece(None));
+                    piece_size -= old_piece_size;
                 }
 
-                if let Some(session_entry) = &mut session_entry {
-                    session_entry.last_signature = match next_known_signature_idx
-                        .map(|x| log.signature_after.get(x).map(|y| y.clone()))
-                        .flatten()
-                        .flatten()
-                    {
-                        None => log.last_signature,
-                        Some(next_known_signature) => next_known_signature,
-                    }
+                if let Some(SyncMessage::NewContentMessage { new, .. }) = pieces.last() {
+                    let mut session_entry =
+                        new.entry(session_id.clone())
+                            .or_insert_with(|| SessionNewContent {
+                                after: first_new_tx_idx,
+                                new_transactions: vec![],
+                                last_signature: Signature::default(),
+                            });
+                    session_entry
+                        .new_transactions
+                        .extend(new_transactions.iter().cloned());
+                    session_entry.last_signature = next_known_signature_idx
+                        .and_then(|x| log.signature_after.get(x).copied().flatten())
+                        .unwrap_or(log.last_signature);
                 }
 
-                sent_state.insert(
-                    session_id.clone(),
-                    sent_state_for_session_id.unwrap_or(known_state_for_session_id.unwrap_or(0))
-                        + n_new_tx,
-                );
+                sent_state.insert(session_id, after_last_new_tx_idx);
             }
+            sessions_to_do = sessions_to_do_again;
         }
 
         let pieces_with_content: Vec<_> = pieces.into_par_iter().filter(|x| matches!(x, SyncMessage::NewContentMessage { new, .. } if !new.is_empty()) || matches!(x, SyncMessage::NewContentMessage { header, .. } if header.is_some())).collect();
@@ -459,3 +1120,292 @@ impl VerifiedState {
         Some(pieces_with_content)
     }
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::{
+        covalue::{
+            colist::CoList,
+            comap::CoMap,
+            common::{HeaderMismatch, Ruleset},
+        },
+        crypto::{agent::AgentSecret, provider::tests::FakeCrypto},
+    };
+
+    pub(crate) fn new_state() -> VerifiedState {
+        let header = CoValueHeader::new("comap", Ruleset::UnsafeAllowAll, None);
+        VerifiedState::new(&header.id().unwrap(), &header, &DashMap::new())
+    }
+
+    /// A writer in a new session of a new agent.
+    pub(crate) fn new_writer() -> SessionWriter {
+        let agent_secret = AgentSecret::new_random();
+        SessionWriter::new(
+            SessionID::new_random(agent_secret.agent_id()),
+            agent_secret.signer_secret().clone(),
+        )
+    }
+
+    pub(crate) fn signer_for_session(session_id: &SessionID) -> anyhow::Result<SignerID> {
+        session_id.signer_id(|_| Err(anyhow::anyhow!("Only agent sessions are expected")))
+    }
+
+    /// The content of every session of a state, as a single new content message.
+    pub(crate) fn all_content<C: CryptoProvider>(state: &VerifiedState<C>) -> SyncMessage {
+        SyncMessage::NewContentMessage {
+            id: state.id.clone(),
+            header: Some(state.header.clone()),
+            priority: CoValuePriority::from(&state.header),
+            new: state
+                .sessions
+                .iter()
+                .map(|x| {
+                    let content = SessionNewContent {
+                        after: 0,
+                        new_transactions: x.transactions.clone(),
+                        last_signature: x.last_signature,
+                    };
+                    (x.key().clone(), content)
+                })
+                .collect(),
+        }
+    }
+
+    /// The transactions of [`CONTENT_MESSAGE`](crate::sync::common::tests::CONTENT_MESSAGE), with their privacy first and trusting changes as a string, as cojson writes them.
+    const TRUSTING_TRANSACTION: &str = r#"{"privacy":"trusting","madeAt":1741083362481,"changes":"[{\"key\":\"title\",\"op\":\"set\",\"value\":\"Groceries\"}]"}"#;
+    const PRIVATE_TRANSACTION: &str = r#"{"privacy":"private","madeAt":1741083365002,"keyUsed":"key_z7bPDuhWUBAKKV4Gu","encryptedChanges":"encrypted_UBpGr-qlmo51RgeXy62pOnGY_ijhDLIWiIB7TlA4Kt1v81NaGKb1KGO2N6T9Psw=="}"#;
+
+    fn round_trip(json: &str) -> Transaction {
+        let transaction: Transaction = serde_json::from_str(json).unwrap();
+        assert_eq!(serde_json::to_string(&transaction).unwrap(), json);
+        transaction
+    }
+
+    #[test]
+    fn round_trips_trusting_transaction() {
+        let transaction = round_trip(TRUSTING_TRANSACTION);
+        assert_eq!(transaction.made_at(), 1741083362481);
+        assert_eq!(transaction.key_used(), None);
+        let nonce_material = NonceMaterial::new(
+            "co_z9DPTVJSEDToSX14GgauM9MMQaM".parse().unwrap(),
+            TransactionID::new(
+                "co_zaSjB3WBb3m3JVewfu7pd2S4J69_session_z2BB9xiHvCX8"
+                    .parse()
+                    .unwrap(),
+                0,
+            ),
+        );
+        assert_eq!(
+            transaction
+                .changes::<NativeCrypto>(None, &nonce_material)
+                .unwrap(),
+            [serde_json::json!({"op": "set", "key": "title", "value": "Groceries"})]
+        );
+    }
+
+    #[test]
+    fn round_trips_private_transaction() {
+        let transaction = round_trip(PRIVATE_TRANSACTION);
+        assert_eq!(transaction.made_at(), 1741083365002);
+        assert_eq!(
+            transaction.key_used().unwrap().to_string(),
+            "key_z7bPDuhWUBAKKV4Gu"
+        );
+    }
+
+    #[test]
+    fn reloaded_session_accepts_new_transactions() {
+        let mut state = new_state();
+        let writer = new_writer();
+        for i in 0..5 {
+            state
+                .make_transaction(&writer, &[serde_json::json!({ "tx": i })])
+                .unwrap();
+        }
+        let session_id = writer.session_id();
+        let json = serde_json::to_string(&*state.sessions.get(session_id).unwrap()).unwrap();
+        let log: SessionLog = serde_json::from_str(&json).unwrap();
+        let mut reloaded = VerifiedState::<NativeCrypto>::new(
+            &state.id,
+            &state.header,
+            &DashMap::from_iter([(session_id.clone(), log)]),
+        );
+
+        let new_transactions =
+            [Transaction::new_trusting(1, &[serde_json::json!({ "tx": 5 })]).unwrap()];
+        let expected_new_hash = state
+            .expected_new_hash_after(session_id, &new_transactions)
+            .unwrap()
+            .expected_new_hash;
+        let signature =
+            NativeCrypto::sign(&writer.signer_secret, &expected_new_hash.to_string().into());
+        let signer_id = signer_for_session(session_id).unwrap();
+        for state in [&mut state, &mut reloaded] {
+            state
+                .try_add_transactions(
+                    session_id,
+                    &signer_id,
+                    &new_transactions,
+                    &Some(expected_new_hash),
+                    &signature,
+                    &None,
+                    &None,
+                )
+                .unwrap();
+        }
+
+        let session = reloaded.sessions.get(session_id).unwrap();
+        assert_eq!(session.transactions.len(), 6);
+        assert_eq!(session.last_hash, Some(expected_new_hash));
+        assert_eq!(
+            session.last_hash,
+            state.sessions.get(session_id).unwrap().last_hash
+        );
+    }
+
+    #[test]
+    fn cached_signature_is_not_trusted_for_another_signer() {
+        let mut source = new_state();
+        let writer = new_writer();
+        source
+            .make_transaction(&writer, &[serde_json::json!({ "tx": 0 })])
+            .unwrap();
+        let content = all_content(&source);
+        let signature_cache = Arc::new(SignatureCache::default());
+        let mut verified =
+            VerifiedState::<NativeCrypto>::new(&source.id, &source.header, &DashMap::new())
+                .with_signature_cache(signature_cache.clone());
+        verified
+            .try_add_new_content(&content, signer_for_session)
+            .unwrap();
+        assert_eq!(signature_cache.len(), 1);
+
+        // A state sharing the cache, but expecting the session to be signed by someone else, still verifies the signature.
+        let other_signer = new_writer()
+            .session_id()
+            .signer_id(|_| unreachable!())
+            .unwrap();
+        let mut other =
+            VerifiedState::<NativeCrypto>::new(&source.id, &source.header, &DashMap::new())
+                .with_signature_cache(signature_cache);
+        assert!(
+            other
+                .try_add_new_content(&content, |_| Ok(other_signer.clone()))
+                .is_err()
+        );
+        assert!(other.sessions.is_empty());
+    }
+
+    #[test]
+    fn bad_signature_rejects_only_its_session() {
+        let mut source = new_state();
+        let writers: Vec<_> = (0..3).map(|_| new_writer()).collect();
+        for (i, writer) in writers.iter().enumerate() {
+            for j in 0..2 {
+                source
+                    .make_transaction(writer, &[serde_json::json!({ "writer": i, "tx": j })])
+                    .unwrap();
+            }
+        }
+        let content = all_content(&source);
+        let SyncMessage::NewContentMessage { new, .. } = &content else {
+            unreachable!()
+        };
+        // Sign the second session's content with the first session's signature.
+        let forged_session = writers[1].session_id();
+        let other_signature = new.get(writers[0].session_id()).unwrap().last_signature;
+        new.get_mut(forged_session).unwrap().last_signature = other_signature;
+
+        let mut target =
+            VerifiedState::<NativeCrypto>::new(&source.id, &source.header, &DashMap::new());
+        let error = target
+            .try_add_new_content(&content, signer_for_session)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("1 session(s)"), "{error}");
+        assert!(error.contains(&forged_session.to_string()), "{error}");
+        assert!(!target.sessions.contains_key(forged_session));
+        for writer in [&writers[0], &writers[2]] {
+            let session = target.sessions.get(writer.session_id()).unwrap();
+            let expected = source.sessions.get(writer.session_id()).unwrap();
+            assert_eq!(session.transactions, expected.transactions);
+            assert_eq!(session.last_hash, expected.last_hash);
+        }
+    }
+
+    #[test]
+    fn new_content_since_splits_large_sessions_at_signatures() {
+        let mut state = new_state();
+        let writer = new_writer();
+        let large = "x".repeat(MAX_RECOMMENDED_TX_SIZE / 2);
+        for _ in 0..5 {
+            state
+                .make_transaction(&writer, &[serde_json::json!(large)])
+                .unwrap();
+        }
+
+        let pieces = state.new_content_since(&None).unwrap();
+        assert!(pieces.len() > 1);
+        let mut peer: VerifiedState = VerifiedState::new(&state.id, &state.header, &DashMap::new());
+        for piece in &pieces {
+            peer.try_add_new_content(piece, signer_for_session).unwrap();
+        }
+        assert_eq!(
+            peer.known_state_uncached()
+                .sessions
+                .into_iter()
+                .collect::<HashMap<_, _>>(),
+            HashMap::from([(writer.session_id().clone(), 5)])
+        );
+    }
+
+    #[test]
+    fn hashes_with_its_crypto_provider() {
+        let header = CoValueHeader::new("comap", Ruleset::UnsafeAllowAll, None);
+        let id = header.id_with::<FakeCrypto>().unwrap();
+        assert_eq!(id, header.id_with::<FakeCrypto>().unwrap());
+        assert_ne!(id, header.id().unwrap());
+
+        let mut state = VerifiedState::<FakeCrypto>::new(&id, &header, &DashMap::new());
+        let writer = new_writer();
+        for value in 0..2 {
+            state
+                .make_transaction(&writer, &[serde_json::json!(value)])
+                .unwrap();
+        }
+        let content = all_content(&state);
+        let mut peer = VerifiedState::<FakeCrypto>::new(&id, &header, &DashMap::new());
+        peer.try_add_new_content(&content, signer_for_session)
+            .unwrap();
+        assert_eq!(peer.valid_transactions(&HashMap::new()).len(), 2);
+
+        // A peer hashing natively arrives at other hashes, so the signatures do not match them.
+        let mut native_peer: VerifiedState = VerifiedState::new(&id, &header, &DashMap::new());
+        assert!(
+            native_peer
+                .try_add_new_content(&content, signer_for_session)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn loads_only_as_the_kind_of_its_header() {
+        let header = CoValueHeader::new("colist", Ruleset::UnsafeAllowAll, None);
+        let state: VerifiedState =
+            VerifiedState::new(&header.id().unwrap(), &header, &DashMap::new());
+        let error = state
+            .load_as(&CoID::<CoMap>::new(state.id.clone()))
+            .unwrap_err();
+        assert!(error.downcast_ref::<HeaderMismatch>().is_some());
+        assert!(
+            state
+                .load_as(&CoID::<CoList>::new(state.id.clone()))
+                .is_ok()
+        );
+
+        let id = header.id_with::<FakeCrypto>().unwrap();
+        let state = VerifiedState::<FakeCrypto>::new(&id, &header, &DashMap::new());
+        assert!(state.load_as(&CoID::<CoList>::new(id)).is_ok());
+    }
+}
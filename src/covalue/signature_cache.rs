@@ -0,0 +1,97 @@
+use crate::{
+    crypto::{hash::Hash, sign::Signature},
+    id::{session_id::SessionID, signer_id::SignerID},
+};
+use lru::LruCache;
+use std::{
+    num::NonZeroUsize,
+    sync::{Mutex, PoisonError},
+};
+
+/// The number of checkpoints a [`SignatureCache`] holds by default.
+pub const DEFAULT_SIGNATURE_CACHE_CAPACITY: usize = 4096;
+
+/// A bounded record of session checkpoints whose signatures have already been verified.
+///
+/// A checkpoint is a session, the signer its signature was verified against, the number of transactions in it, and its hash after those transactions.
+/// Content arriving again at a known checkpoint with the same signature and signer is accepted after comparing hashes, without verifying the signature again.
+#[derive(Debug)]
+pub struct SignatureCache {
+    checkpoints: Mutex<LruCache<(SessionID, SignerID, usize, Hash), Signature>>,
+}
+
+impl Default for SignatureCache {
+    fn default() -> Self {
+        Self::new(
+            NonZeroUsize::new(DEFAULT_SIGNATURE_CACHE_CAPACITY)
+                .expect("Default capacity should be non-zero"),
+        )
+    }
+}
+
+impl SignatureCache {
+    /// Creates a cache holding at most `capacity` checkpoints, forgetting the least recently used first.
+    pub fn new(capacity: NonZeroUsize) -> Self {
+        Self {
+            checkpoints: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+
+    /// Whether a signature was already verified for a session at the given checkpoint.
+    ///
+    /// # Arguments
+    ///
+    /// * `session_id` - The ID of the session.
+    ///
+    /// * `signer_id` - The signer the signature is expected to be made by.
+    ///
+    /// * `tx_count` - The number of transactions in the session at the checkpoint.
+    ///
+    /// * `hash` - The hash of the session at the checkpoint.
+    ///
+    /// * `signature` - The signature of the hash.
+    pub fn contains(
+        &self,
+        session_id: &SessionID,
+        signer_id: &SignerID,
+        tx_count: usize,
+        hash: &Hash,
+        signature: &Signature,
+    ) -> bool {
+        self.checkpoints
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&(session_id.clone(), signer_id.clone(), tx_count, *hash))
+            .is_some_and(|x| x == signature)
+    }
+
+    /// Records that a signature was verified against a signer for a session at the given checkpoint.
+    pub fn insert(
+        &self,
+        session_id: &SessionID,
+        signer_id: &SignerID,
+        tx_count: usize,
+        hash: &Hash,
+        signature: &Signature,
+    ) {
+        self.checkpoints
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .put(
+                (session_id.clone(), signer_id.clone(), tx_count, *hash),
+                *signature,
+            );
+    }
+
+    /// The number of checkpoints in the cache.
+    pub fn len(&self) -> usize {
+        self.checkpoints
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
@@ -0,0 +1,105 @@
+use super::{seal::SealerSecret, seed::SecretSeed, sign::SignerSecret};
+use crate::id::{agent_id::AgentID, sealer_id::SealerID, signer_id::SignerID};
+use std::str::FromStr;
+use subtle::ConstantTimeEq;
+use zeroize::Zeroizing;
+
+/// The secret identity of an agent, able to both sign transactions and unseal messages sealed for it.
+///
+/// Agent secrets are neither serialised nor displayed; their string form is only available through [`AgentSecret::expose_secret`].
+#[derive(Debug, Clone)]
+pub struct AgentSecret {
+    sealer_secret: SealerSecret,
+    signer_secret: SignerSecret,
+}
+impl AgentSecret {
+    pub fn new(sealer_secret: SealerSecret, signer_secret: SignerSecret) -> Self {
+        Self {
+            sealer_secret,
+            signer_secret,
+        }
+    }
+    pub fn new_random() -> Self {
+        Self::new(SealerSecret::new_random(), SignerSecret::new_random())
+    }
+    /// Derives an agent secret from a secret seed; the same seed always yields the same agent.
+    pub fn from_secret_seed(secret_seed: &SecretSeed) -> Self {
+        secret_seed.agent_secret()
+    }
+    pub fn sealer_secret(&self) -> &SealerSecret {
+        &self.sealer_secret
+    }
+    pub fn signer_secret(&self) -> &SignerSecret {
+        &self.signer_secret
+    }
+    pub fn sealer_id(&self) -> SealerID {
+        self.sealer_secret.sealer_id()
+    }
+    pub fn signer_id(&self) -> SignerID {
+        SignerID::new(self.signer_secret.verifying_key())
+    }
+    pub fn agent_id(&self) -> AgentID {
+        AgentID::new(self.sealer_id(), self.signer_id())
+    }
+    /// The secret as its sealer secret followed by `/` followed by its signer secret, as cojson writes it; take care not to copy or log it.
+    pub fn expose_secret(&self) -> Zeroizing<String> {
+        let mut secret = self.sealer_secret.expose_secret();
+        secret.push('/');
+        secret.push_str(&self.signer_secret.expose_secret());
+        secret
+    }
+}
+
+impl FromStr for AgentSecret {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        s.split_once('/').and_then(|(x, y)| {
+            SealerSecret::from_str(x).ok().zip(SignerSecret::from_str(y).ok()).map(|(sealer_secret, signer_secret)| Self::new(sealer_secret, signer_secret))
+        }).ok_or(anyhow::anyhow!("String not a valid agent secret; agent secrets consist of a sealer secret followed by `/` followed by a signer secret"))
+    }
+}
+
+impl ConstantTimeEq for AgentSecret {
+    fn ct_eq(&self, other: &Self) -> subtle::Choice {
+        self.sealer_secret.ct_eq(&other.sealer_secret)
+            & self.signer_secret.ct_eq(&other.signer_secret)
+    }
+}
+
+impl PartialEq for AgentSecret {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl Eq for AgentSecret {}
+
+impl From<&AgentSecret> for AgentID {
+    fn from(agent_secret: &AgentSecret) -> AgentID {
+        agent_secret.agent_id()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exposed_secret_parses_back() {
+        let agent_secret = AgentSecret::new_random();
+        let exposed = agent_secret.expose_secret();
+        assert!(exposed.starts_with("sealerSecret_z"));
+        assert!(exposed.contains("/signerSecret_z"));
+        assert!(exposed.parse::<AgentSecret>().unwrap() == agent_secret);
+        assert!(AgentSecret::new_random() != agent_secret);
+    }
+
+    #[test]
+    fn debug_redacts_secrets() {
+        let agent_secret = AgentSecret::new_random();
+        let debug = format!("{agent_secret:?}");
+        assert!(debug.contains("[REDACTED]"));
+        assert!(!debug.contains(agent_secret.expose_secret().as_str()));
+    }
+}
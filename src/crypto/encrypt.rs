@@ -0,0 +1,197 @@
+use super::provider::{CryptoProvider, NativeCrypto};
+use crate::id::{common::TransactionID, key_id::KeyID, rawcoid::RawCoID};
+use rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use subtle::ConstantTimeEq;
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+pub const KEY_SECRET_LENGTH: usize = 32;
+pub const NONCE_LENGTH: usize = 24;
+
+/// The material a nonce is derived from, binding a ciphertext to a single transaction of a [`CoValue`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct NonceMaterial {
+    #[serde(rename = "in")]
+    pub in_: RawCoID,
+    pub tx: TransactionID,
+}
+
+impl NonceMaterial {
+    pub fn new(id: RawCoID, tx: TransactionID) -> Self {
+        Self { in_: id, tx }
+    }
+
+    pub fn nonce(&self) -> anyhow::Result<[u8; NONCE_LENGTH]> {
+        NativeCrypto::nonce(self)
+    }
+}
+
+/// A symmetric key, wiped from memory when dropped.
+///
+/// Key secrets are neither serialised nor displayed; their string form is only available through [`KeySecret::expose_secret`].
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct KeySecret([u8; KEY_SECRET_LENGTH]);
+impl KeySecret {
+    pub fn new(bytes: [u8; KEY_SECRET_LENGTH]) -> Self {
+        Self(bytes)
+    }
+
+    /// Generates a new random key secret along with a new random ID to refer to it by.
+    pub fn new_random() -> (Self, KeyID) {
+        let mut key_secret = Self([0u8; KEY_SECRET_LENGTH]);
+        OsRng.fill_bytes(&mut key_secret.0);
+        (key_secret, KeyID::new_random())
+    }
+
+    /// The raw bytes of the key; take care not to copy or log them.
+    pub fn expose_bytes(&self) -> &[u8; KEY_SECRET_LENGTH] {
+        &self.0
+    }
+
+    /// The key as a string beginning with `keySecret_z`, as cojson writes it; take care not to copy or log it.
+    pub fn expose_secret(&self) -> Zeroizing<String> {
+        Zeroizing::new(format!(
+            "keySecret_z{}",
+            bs58::encode(&self.0).into_string()
+        ))
+    }
+
+    pub fn encrypt(
+        &self,
+        value: impl Into<serde_json::value::Value>,
+        nonce_material: &NonceMaterial,
+    ) -> anyhow::Result<Vec<u8>> {
+        NativeCrypto::encrypt(self, &value.into(), nonce_material)
+    }
+
+    pub fn decrypt(
+        &self,
+        ciphertext: &[u8],
+        nonce_material: &NonceMaterial,
+    ) -> anyhow::Result<serde_json::value::Value> {
+        NativeCrypto::decrypt(self, ciphertext, nonce_material)
+    }
+
+    /// Encrypts the changes of a transaction, using a nonce derived from the transaction's ID.
+    pub fn encrypt_changes(
+        &self,
+        changes: &[serde_json::value::Value],
+        nonce_material: &NonceMaterial,
+    ) -> anyhow::Result<Vec<u8>> {
+        self.encrypt(changes.to_vec(), nonce_material)
+    }
+
+    /// Decrypts the changes of a transaction, using a nonce derived from the transaction's ID.
+    pub fn decrypt_changes(
+        &self,
+        encrypted_changes: &[u8],
+        nonce_material: &NonceMaterial,
+    ) -> anyhow::Result<Vec<serde_json::value::Value>> {
+        match self.decrypt(encrypted_changes, nonce_material)? {
+            serde_json::value::Value::Array(changes) => Ok(changes),
+            _ => Err(anyhow::anyhow!(
+                "Decrypted changes are not a list of changes"
+            )),
+        }
+    }
+}
+
+impl std::fmt::Debug for KeySecret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("KeySecret([REDACTED])")
+    }
+}
+
+impl FromStr for KeySecret {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        s.split_once("keySecret_z").and_then(|(_, y)| {
+            bs58::decode(y).into_vec().ok().and_then(|z| z.try_into().map(Self).ok())
+        }).ok_or(anyhow::anyhow!("String not a valid key secret; key secrets begin with `keySecret_z` followed by a Base58-encoded symmetric key"))
+    }
+}
+
+impl ConstantTimeEq for KeySecret {
+    fn ct_eq(&self, other: &Self) -> subtle::Choice {
+        self.0.ct_eq(&other.0)
+    }
+}
+
+impl PartialEq for KeySecret {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl Eq for KeySecret {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        crypto::{
+            seed::{SECRET_SEED_LENGTH, SecretSeed},
+            short_hash::SHORT_HASH_LENGTH,
+        },
+        id::session_id::SessionID,
+    };
+    use serde_json::json;
+
+    fn nonce_material(tx_index: usize) -> NonceMaterial {
+        let agent_id = SecretSeed::new([0; SECRET_SEED_LENGTH])
+            .agent_secret()
+            .agent_id();
+        NonceMaterial::new(
+            RawCoID::new([1; SHORT_HASH_LENGTH]),
+            TransactionID::new(SessionID::new(agent_id, "1".into()), tx_index),
+        )
+    }
+
+    fn changes() -> Vec<serde_json::Value> {
+        vec![
+            json!({"op": "set", "key": "name", "value": "Alice"}),
+            json!({"op": "del", "key": "age"}),
+        ]
+    }
+
+    #[test]
+    fn round_trips_changes() {
+        let key_secret = KeySecret::new([2; KEY_SECRET_LENGTH]);
+        let encrypted = key_secret
+            .encrypt_changes(&changes(), &nonce_material(0))
+            .unwrap();
+        assert_eq!(
+            key_secret
+                .decrypt_changes(&encrypted, &nonce_material(0))
+                .unwrap(),
+            changes()
+        );
+    }
+
+    #[test]
+    fn cannot_decrypt_changes_of_another_transaction() {
+        let key_secret = KeySecret::new([2; KEY_SECRET_LENGTH]);
+        let encrypted = key_secret
+            .encrypt_changes(&changes(), &nonce_material(0))
+            .unwrap();
+        assert!(
+            key_secret
+                .decrypt_changes(&encrypted, &nonce_material(1))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn cannot_decrypt_changes_with_another_key() {
+        let encrypted = KeySecret::new([2; KEY_SECRET_LENGTH])
+            .encrypt_changes(&changes(), &nonce_material(0))
+            .unwrap();
+        assert!(
+            KeySecret::new([3; KEY_SECRET_LENGTH])
+                .decrypt_changes(&encrypted, &nonce_material(0))
+                .is_err()
+        );
+    }
+}
@@ -1,15 +1,14 @@
+use super::provider::{CryptoProvider, NativeCrypto};
 use serde::{Deserialize, Serialize};
 use std::{fmt::Display, str::FromStr};
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
-#[serde(rename_all = "camelCase")]
+#[serde(into = "String", try_from = "String")]
 pub struct Hash(pub(crate) [u8; blake3::OUT_LEN]);
 
 impl Hash {
     pub fn new<T: Into<serde_json::value::Value>>(value: T) -> Self {
-        let json_value = value.into();
-        let json_string = format!("{:#}", json_value);
-        Self(blake3::hash(json_string.as_bytes()).as_bytes().to_owned())
+        NativeCrypto::hash(&value.into())
     }
 }
 
@@ -28,3 +27,17 @@ impl Display for Hash {
         write!(f, "hash_z{}", bs58::encode(&self.0).into_string())
     }
 }
+
+impl From<Hash> for String {
+    fn from(value: Hash) -> String {
+        value.to_string()
+    }
+}
+
+impl TryFrom<String> for Hash {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> anyhow::Result<Self> {
+        value.parse()
+    }
+}
@@ -0,0 +1,410 @@
+use super::sign::SignerSecret;
+use crate::id::signer_id::SignerID;
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::{
+    XChaCha20Poly1305, XNonce,
+    aead::{Aead, KeyInit, Payload},
+};
+use rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::Path,
+};
+use zeroize::Zeroizing;
+
+/// The version of the keystore format written by this crate.
+pub const KEYSTORE_VERSION: u32 = 1;
+pub const KEYSTORE_SALT_LENGTH: usize = 16;
+pub const KEYSTORE_NONCE_LENGTH: usize = 24;
+const KEYSTORE_KEY_LENGTH: usize = 32;
+/// The most memory, in KiB, keys may be derived with (1 GiB), so that a keystore cannot exhaust memory when unlocked.
+pub const MAX_KDF_MEMORY_COST: u32 = 1024 * 1024;
+/// The most passes over memory keys may be derived with.
+pub const MAX_KDF_TIME_COST: u32 = 64;
+/// The most lanes keys may be derived with.
+pub const MAX_KDF_PARALLELISM: u32 = 64;
+
+/// Parameters of the Argon2id function deriving encryption keys from passwords.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct KdfParams {
+    /// Memory used, in KiB.
+    pub memory_cost: u32,
+    /// Number of passes over the memory.
+    pub time_cost: u32,
+    /// Number of lanes.
+    pub parallelism: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        Self {
+            memory_cost: Params::DEFAULT_M_COST,
+            time_cost: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+impl KdfParams {
+    /// Checks that deriving a key with these parameters would not use an unreasonable amount of memory or time.
+    pub fn check(&self) -> anyhow::Result<()> {
+        for (name, value, max) in [
+            ("memory cost", self.memory_cost, MAX_KDF_MEMORY_COST),
+            ("time cost", self.time_cost, MAX_KDF_TIME_COST),
+            ("parallelism", self.parallelism, MAX_KDF_PARALLELISM),
+        ] {
+            if value > max {
+                return Err(anyhow::anyhow!(
+                    "Key derivation {name} of {value} exceeds the maximum of {max}"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn derive_key(
+        &self,
+        password: &[u8],
+        salt: &[u8; KEYSTORE_SALT_LENGTH],
+    ) -> anyhow::Result<Zeroizing<[u8; KEYSTORE_KEY_LENGTH]>> {
+        self.check()?;
+        let params = Params::new(
+            self.memory_cost,
+            self.time_cost,
+            self.parallelism,
+            Some(KEYSTORE_KEY_LENGTH),
+        )
+        .map_err(|e| anyhow::anyhow!("Invalid key derivation parameters: {e}"))?;
+        let mut key = Zeroizing::new([0u8; KEYSTORE_KEY_LENGTH]);
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(password, salt, key.as_mut())
+            .map_err(|e| anyhow::anyhow!("Unable to derive key from password: {e}"))?;
+        Ok(key)
+    }
+}
+
+/// A signer secret encrypted under a key derived from a password.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+struct KeystoreEntry {
+    #[serde(rename = "signerID")]
+    signer_id: SignerID,
+    kdf: KdfParams,
+    #[serde(with = "base58")]
+    salt: [u8; KEYSTORE_SALT_LENGTH],
+    #[serde(with = "base58")]
+    nonce: [u8; KEYSTORE_NONCE_LENGTH],
+    /// The signer secret's string form, encrypted with XChaCha20-Poly1305 and authenticated along with the signer ID.
+    #[serde(with = "base58")]
+    ciphertext: Vec<u8>,
+}
+
+/// Password-protected storage for signer secrets at rest.
+///
+/// Each secret is encrypted with XChaCha20-Poly1305 under a key derived from its password with Argon2id, using its own random salt and nonce.
+/// Identities are listed, added, removed, and unlocked by their [`SignerID`], which is kept in the clear.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Keystore {
+    #[serde(deserialize_with = "deserialize_version")]
+    version: u32,
+    /// Parameters used when deriving keys for newly added secrets.
+    kdf: KdfParams,
+    entries: Vec<KeystoreEntry>,
+}
+
+impl Default for Keystore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Keystore {
+    /// Creates an empty keystore, deriving keys with the default Argon2id parameters.
+    pub fn new() -> Self {
+        Self::with_kdf_params(KdfParams::default())
+    }
+
+    /// Creates an empty keystore, deriving keys for newly added secrets with the given Argon2id parameters.
+    pub fn with_kdf_params(kdf: KdfParams) -> Self {
+        Self {
+            version: KEYSTORE_VERSION,
+            kdf,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Reads a keystore from a file.
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        Ok(serde_json::from_slice(&fs::read(path)?)?)
+    }
+
+    /// Writes the keystore to a file, replacing it if it exists.
+    ///
+    /// The keystore is written to a temporary file in the same directory, readable only by its owner, which then replaces the file.
+    /// A crash while saving leaves either the old or the new keystore, never a partly written one.
+    pub fn save(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        let file_name = path.file_name().ok_or(anyhow::anyhow!(
+            "Keystore path {} is not a file",
+            path.display()
+        ))?;
+        let mut suffix = [0u8; 8];
+        OsRng.fill_bytes(&mut suffix);
+        let temp_path = path.with_file_name(format!(
+            ".{}.{}.tmp",
+            file_name.to_string_lossy(),
+            bs58::encode(suffix).into_string()
+        ));
+        let result = self.write_new(&temp_path).and_then(|()| {
+            fs::rename(&temp_path, path)?;
+            sync_parent_dir(path)
+        });
+        if result.is_err() {
+            let _ = fs::remove_file(&temp_path);
+        }
+        result
+    }
+
+    /// Writes the keystore to a new file readable only by its owner, flushing it to disk.
+    fn write_new(&self, path: &Path) -> anyhow::Result<()> {
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(path)?;
+        file.write_all(&serde_json::to_vec_pretty(self)?)?;
+        file.sync_all()?;
+        Ok(())
+    }
+
+    /// The IDs of the signers whose secrets are stored, in the order they were added.
+    pub fn signer_ids(&self) -> impl Iterator<Item = &SignerID> {
+        self.entries.iter().map(|entry| &entry.signer_id)
+    }
+
+    pub fn contains(&self, signer_id: &SignerID) -> bool {
+        self.entries
+            .iter()
+            .any(|entry| &entry.signer_id == signer_id)
+    }
+
+    /// Encrypts a signer secret under a password and stores it.
+    ///
+    /// # Arguments
+    ///
+    /// * `signer_secret` - The secret to store.
+    ///
+    /// * `password` - The password needed to unlock the secret.
+    ///
+    /// # Returns
+    ///
+    /// The ID of the signer whose secret was stored.
+    pub fn add(
+        &mut self,
+        signer_secret: &SignerSecret,
+        password: &[u8],
+    ) -> anyhow::Result<SignerID> {
+        let signer_id = SignerID::new(signer_secret.verifying_key());
+        if self.contains(&signer_id) {
+            return Err(anyhow::anyhow!("Keystore already contains {signer_id}"));
+        }
+        let mut salt = [0u8; KEYSTORE_SALT_LENGTH];
+        OsRng.fill_bytes(&mut salt);
+        let mut nonce = [0u8; KEYSTORE_NONCE_LENGTH];
+        OsRng.fill_bytes(&mut nonce);
+        let key = self.kdf.derive_key(password, &salt)?;
+        let signer_id_string = signer_id.to_string();
+        let plaintext = signer_secret.expose_secret();
+        let ciphertext = XChaCha20Poly1305::new(key.as_ref().into())
+            .encrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: plaintext.as_bytes(),
+                    aad: signer_id_string.as_bytes(),
+                },
+            )
+            .map_err(|_| anyhow::anyhow!("Unable to encrypt secret of {signer_id}"))?;
+        self.entries.push(KeystoreEntry {
+            signer_id: signer_id.clone(),
+            kdf: self.kdf,
+            salt,
+            nonce,
+            ciphertext,
+        });
+        Ok(signer_id)
+    }
+
+    /// Removes the secret of a signer from the keystore.
+    pub fn remove(&mut self, signer_id: &SignerID) -> anyhow::Result<()> {
+        let index = self
+            .entries
+            .iter()
+            .position(|entry| &entry.signer_id == signer_id)
+            .ok_or(anyhow::anyhow!("Keystore does not contain {signer_id}"))?;
+        self.entries.remove(index);
+        Ok(())
+    }
+
+    /// Decrypts the secret of a signer with its password.
+    pub fn unlock(&self, signer_id: &SignerID, password: &[u8]) -> anyhow::Result<SignerSecret> {
+        let entry = self
+            .entries
+            .iter()
+            .find(|entry| &entry.signer_id == signer_id)
+            .ok_or(anyhow::anyhow!("Keystore does not contain {signer_id}"))?;
+        let key = entry.kdf.derive_key(password, &entry.salt)?;
+        let signer_id_string = signer_id.to_string();
+        let plaintext = XChaCha20Poly1305::new(key.as_ref().into())
+            .decrypt(
+                XNonce::from_slice(&entry.nonce),
+                Payload {
+                    msg: &entry.ciphertext,
+                    aad: signer_id_string.as_bytes(),
+                },
+            )
+            .map_err(|_| {
+                anyhow::anyhow!("Unable to unlock {signer_id}; the password may be incorrect")
+            })
+            .map(Zeroizing::new)?;
+        let signer_secret: SignerSecret = std::str::from_utf8(&plaintext)?.parse()?;
+        if &SignerID::new(signer_secret.verifying_key()) != signer_id {
+            return Err(anyhow::anyhow!(
+                "Secret stored for {signer_id} belongs to a different signer"
+            ));
+        }
+        Ok(signer_secret)
+    }
+}
+
+/// Flushes the directory containing a file to disk, so that a file just renamed into it survives a crash.
+fn sync_parent_dir(path: &Path) -> anyhow::Result<()> {
+    #[cfg(unix)]
+    if let Some(parent) = path.parent() {
+        let parent = if parent.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            parent
+        };
+        fs::File::open(parent)?.sync_all()?;
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+    Ok(())
+}
+
+fn deserialize_version<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+    let version = u32::deserialize(deserializer)?;
+    if version != KEYSTORE_VERSION {
+        return Err(serde::de::Error::custom(format!(
+            "Unsupported keystore version {version}; expected version {KEYSTORE_VERSION}"
+        )));
+    }
+    Ok(version)
+}
+
+/// Byte strings in the keystore are stored in Base58.
+mod base58 {
+    use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+    pub fn serialize<S: Serializer>(
+        bytes: impl AsRef<[u8]>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&bs58::encode(bytes).into_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, T: TryFrom<Vec<u8>>>(
+        deserializer: D,
+    ) -> Result<T, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        bs58::decode(&s)
+            .into_vec()
+            .ok()
+            .and_then(|bytes| T::try_from(bytes).ok())
+            .ok_or(D::Error::custom(
+                "String not valid keystore data; expected Base58-encoded bytes of the right length",
+            ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parameters cheap enough for tests; far too weak to protect real secrets.
+    const TEST_KDF_PARAMS: KdfParams = KdfParams {
+        memory_cost: 8,
+        time_cost: 1,
+        parallelism: 1,
+    };
+
+    fn temp_dir() -> std::path::PathBuf {
+        let mut suffix = [0u8; 8];
+        OsRng.fill_bytes(&mut suffix);
+        let dir = std::env::temp_dir().join(format!(
+            "jazz-rs-keystore-{}",
+            bs58::encode(suffix).into_string()
+        ));
+        fs::create_dir(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn saves_and_loads() {
+        let dir = temp_dir();
+        let path = dir.join("keystore.json");
+        let mut keystore = Keystore::with_kdf_params(TEST_KDF_PARAMS);
+        let signer_secret = SignerSecret::new_random();
+        let signer_id = keystore.add(&signer_secret, b"password").unwrap();
+        fs::write(&path, "old keystore").unwrap();
+        keystore.save(&path).unwrap();
+
+        let loaded = Keystore::load(&path).unwrap();
+        assert_eq!(loaded, keystore);
+        assert_eq!(
+            loaded
+                .unlock(&signer_id, b"password")
+                .unwrap()
+                .verifying_key(),
+            signer_secret.verifying_key()
+        );
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(
+                fs::metadata(&path).unwrap().permissions().mode() & 0o777,
+                0o600
+            );
+        }
+        // Only the keystore itself is left behind, not the temporary file it was written to.
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn rejects_excessive_kdf_params() {
+        for kdf in [
+            KdfParams {
+                memory_cost: MAX_KDF_MEMORY_COST + 1,
+                ..TEST_KDF_PARAMS
+            },
+            KdfParams {
+                time_cost: MAX_KDF_TIME_COST + 1,
+                ..TEST_KDF_PARAMS
+            },
+            KdfParams {
+                parallelism: MAX_KDF_PARALLELISM + 1,
+                ..TEST_KDF_PARAMS
+            },
+        ] {
+            let error = Keystore::with_kdf_params(kdf)
+                .add(&SignerSecret::new_random(), b"password")
+                .unwrap_err();
+            assert!(error.to_string().contains("exceeds the maximum"), "{error}");
+        }
+    }
+}
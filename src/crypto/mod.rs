@@ -1,4 +1,11 @@
+pub mod agent;
+pub mod encrypt;
 pub mod hash;
+pub mod keystore;
+pub mod provider;
+pub mod seal;
+pub mod seed;
 pub mod short_hash;
 pub mod sign;
+pub mod stable_stringify;
 pub mod streaming_hash;
@@ -0,0 +1,355 @@
+use super::{
+    encrypt::{KeySecret, NONCE_LENGTH, NonceMaterial},
+    hash::Hash,
+    seal::SealerSecret,
+    short_hash::ShortHash,
+    sign::{Signature, SignerSecret},
+    stable_stringify::stable_stringify,
+};
+use crate::id::{sealer_id::SealerID, signer_id::SignerID};
+use crypto_box::{SalsaBox, aead::Aead};
+use ed25519_dalek::{Signer, Verifier};
+use salsa20::{
+    XSalsa20,
+    cipher::{KeyIvInit, StreamCipher},
+};
+use serde_json::value::Value;
+use std::fmt::Debug;
+
+/// An implementation of the cryptographic primitives used by Jazz.
+///
+/// Keys, hashes, and signatures have fixed formats shared with other Jazz peers; a provider only decides how the operations on them are carried out.
+/// Implementors need only provide the byte-level primitives, as the operations on JSON values are derived from them.
+pub trait CryptoProvider: Clone + Debug + Default + Send + Sync + 'static {
+    /// The state of an incremental hash.
+    type StreamingHasher: Clone + Debug + Default + Send + Sync;
+
+    /// Hashes a byte string.
+    fn hash_bytes(bytes: &[u8]) -> Hash;
+
+    /// Hashes a byte string, truncating the result.
+    fn short_hash_bytes(bytes: &[u8]) -> ShortHash {
+        ShortHash::from(Self::hash_bytes(bytes))
+    }
+
+    /// Adds a byte string to an incremental hash.
+    fn streaming_hash_update(hasher: &mut Self::StreamingHasher, bytes: &[u8]);
+
+    /// The hash of all byte strings added to an incremental hash so far.
+    fn streaming_hash_digest(hasher: &Self::StreamingHasher) -> Hash;
+
+    /// Signs a byte string.
+    fn sign_bytes(signer_secret: &SignerSecret, message: &[u8]) -> Signature;
+
+    /// Verifies the signature of a byte string.
+    fn verify_bytes(
+        signer_id: &SignerID,
+        message: &[u8],
+        signature: &Signature,
+    ) -> anyhow::Result<()>;
+
+    /// Verifies the signatures of many byte strings at once, failing if any signature is invalid.
+    ///
+    /// Providers able to verify signatures in bulk faster than one at a time should override this.
+    fn verify_batch_bytes(items: &[(&SignerID, &[u8], &Signature)]) -> anyhow::Result<()> {
+        items
+            .iter()
+            .try_for_each(|(signer_id, message, signature)| {
+                Self::verify_bytes(signer_id, message, signature)
+            })
+    }
+
+    /// Encrypts a byte string for the holder of the recipient's sealer secret, authenticated as coming from the sender.
+    fn seal_bytes(
+        from: &SealerSecret,
+        to: &SealerID,
+        nonce: &[u8; NONCE_LENGTH],
+        plaintext: &[u8],
+    ) -> anyhow::Result<Vec<u8>>;
+
+    /// Decrypts a byte string sealed for the holder of the sealer secret, checking it came from the sender.
+    fn unseal_bytes(
+        sealer_secret: &SealerSecret,
+        from: &SealerID,
+        nonce: &[u8; NONCE_LENGTH],
+        ciphertext: &[u8],
+    ) -> anyhow::Result<Vec<u8>>;
+
+    /// Encrypts a byte string with a symmetric key.
+    fn encrypt_bytes(
+        key_secret: &KeySecret,
+        nonce: &[u8; NONCE_LENGTH],
+        plaintext: &[u8],
+    ) -> anyhow::Result<Vec<u8>>;
+
+    /// Decrypts a byte string with a symmetric key.
+    fn decrypt_bytes(
+        key_secret: &KeySecret,
+        nonce: &[u8; NONCE_LENGTH],
+        ciphertext: &[u8],
+    ) -> anyhow::Result<Vec<u8>>;
+
+    fn hash(value: &Value) -> Hash {
+        Self::hash_bytes(stable_stringify(value).as_bytes())
+    }
+
+    fn short_hash(value: &Value) -> ShortHash {
+        Self::short_hash_bytes(stable_stringify(value).as_bytes())
+    }
+
+    fn streaming_hash(hasher: &mut Self::StreamingHasher, value: &Value) {
+        Self::streaming_hash_update(hasher, stable_stringify(value).as_bytes())
+    }
+
+    fn sign(signer_secret: &SignerSecret, message: &Value) -> Signature {
+        Self::sign_bytes(signer_secret, stable_stringify(message).as_bytes())
+    }
+
+    fn verify(signer_id: &SignerID, message: &Value, signature: &Signature) -> anyhow::Result<()> {
+        Self::verify_bytes(signer_id, stable_stringify(message).as_bytes(), signature)
+    }
+
+    fn verify_batch(items: &[(&SignerID, &Value, &Signature)]) -> anyhow::Result<()> {
+        let messages: Vec<_> = items
+            .iter()
+            .map(|(_, message, _)| stable_stringify(message))
+            .collect();
+        let items: Vec<_> = items
+            .iter()
+            .zip(messages.iter())
+            .map(|((signer_id, _, signature), message)| {
+                (*signer_id, message.as_bytes(), *signature)
+            })
+            .collect();
+        Self::verify_batch_bytes(&items)
+    }
+
+    /// Derives a nonce from the ID of a [`CoValue`] and one of its transactions.
+    fn nonce(nonce_material: &NonceMaterial) -> anyhow::Result<[u8; NONCE_LENGTH]> {
+        Ok(
+            Self::hash(&serde_json::to_value(nonce_material)?).0[..NONCE_LENGTH]
+                .try_into()
+                .expect("Array should have `NONCE_LENGTH` bytes"),
+        )
+    }
+
+    fn seal(
+        from: &SealerSecret,
+        to: &SealerID,
+        message: &Value,
+        nonce_material: &NonceMaterial,
+    ) -> anyhow::Result<Vec<u8>> {
+        Self::seal_bytes(
+            from,
+            to,
+            &Self::nonce(nonce_material)?,
+            stable_stringify(message).as_bytes(),
+        )
+    }
+
+    fn unseal(
+        sealer_secret: &SealerSecret,
+        from: &SealerID,
+        sealed: &[u8],
+        nonce_material: &NonceMaterial,
+    ) -> anyhow::Result<Value> {
+        let plaintext =
+            Self::unseal_bytes(sealer_secret, from, &Self::nonce(nonce_material)?, sealed)?;
+        Ok(serde_json::from_slice(&plaintext)?)
+    }
+
+    fn encrypt(
+        key_secret: &KeySecret,
+        value: &Value,
+        nonce_material: &NonceMaterial,
+    ) -> anyhow::Result<Vec<u8>> {
+        Self::encrypt_bytes(
+            key_secret,
+            &Self::nonce(nonce_material)?,
+            stable_stringify(value).as_bytes(),
+        )
+    }
+
+    fn decrypt(
+        key_secret: &KeySecret,
+        ciphertext: &[u8],
+        nonce_material: &NonceMaterial,
+    ) -> anyhow::Result<Value> {
+        let plaintext = Self::decrypt_bytes(key_secret, &Self::nonce(nonce_material)?, ciphertext)?;
+        Ok(serde_json::from_slice(&plaintext)?)
+    }
+}
+
+/// The default [`CryptoProvider`], using BLAKE3 for hashing, Ed25519 for signing, X25519 with XSalsa20-Poly1305 for sealing, and XSalsa20 for encryption.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NativeCrypto;
+
+impl CryptoProvider for NativeCrypto {
+    type StreamingHasher = blake3::Hasher;
+
+    fn hash_bytes(bytes: &[u8]) -> Hash {
+        Hash(*blake3::hash(bytes).as_bytes())
+    }
+
+    fn streaming_hash_update(hasher: &mut Self::StreamingHasher, bytes: &[u8]) {
+        hasher.update(bytes);
+    }
+
+    fn streaming_hash_digest(hasher: &Self::StreamingHasher) -> Hash {
+        Hash(*hasher.finalize().as_bytes())
+    }
+
+    fn sign_bytes(signer_secret: &SignerSecret, message: &[u8]) -> Signature {
+        Signature::new(signer_secret.expose_signing_key().sign(message))
+    }
+
+    fn verify_bytes(
+        signer_id: &SignerID,
+        message: &[u8],
+        signature: &Signature,
+    ) -> anyhow::Result<()> {
+        Ok(signer_id
+            .verifying_key()
+            .verify(message, &signature.into())?)
+    }
+
+    fn verify_batch_bytes(items: &[(&SignerID, &[u8], &Signature)]) -> anyhow::Result<()> {
+        let messages: Vec<_> = items.iter().map(|(_, message, _)| *message).collect();
+        let signatures: Vec<_> = items
+            .iter()
+            .map(|(_, _, signature)| ed25519_dalek::Signature::from(*signature))
+            .collect();
+        let verifying_keys: Vec<_> = items
+            .iter()
+            .map(|(signer_id, _, _)| *signer_id.verifying_key())
+            .collect();
+        Ok(ed25519_dalek::verify_batch(
+            &messages,
+            &signatures,
+            &verifying_keys,
+        )?)
+    }
+
+    fn seal_bytes(
+        from: &SealerSecret,
+        to: &SealerID,
+        nonce: &[u8; NONCE_LENGTH],
+        plaintext: &[u8],
+    ) -> anyhow::Result<Vec<u8>> {
+        SalsaBox::new(
+            &crypto_box::PublicKey::from_bytes(*to.as_bytes()),
+            &crypto_box::SecretKey::from_bytes(*from.expose_bytes()),
+        )
+        .encrypt(nonce.into(), plaintext)
+        .map_err(|_| anyhow::anyhow!("Unable to seal message for {to}"))
+    }
+
+    fn unseal_bytes(
+        sealer_secret: &SealerSecret,
+        from: &SealerID,
+        nonce: &[u8; NONCE_LENGTH],
+        ciphertext: &[u8],
+    ) -> anyhow::Result<Vec<u8>> {
+        SalsaBox::new(
+            &crypto_box::PublicKey::from_bytes(*from.as_bytes()),
+            &crypto_box::SecretKey::from_bytes(*sealer_secret.expose_bytes()),
+        )
+        .decrypt(nonce.into(), ciphertext)
+        .map_err(|_| anyhow::anyhow!("Unable to unseal message from {from}"))
+    }
+
+    fn encrypt_bytes(
+        key_secret: &KeySecret,
+        nonce: &[u8; NONCE_LENGTH],
+        plaintext: &[u8],
+    ) -> anyhow::Result<Vec<u8>> {
+        let mut data = plaintext.to_vec();
+        XSalsa20::new(key_secret.expose_bytes().into(), nonce.into()).apply_keystream(&mut data);
+        Ok(data)
+    }
+
+    fn decrypt_bytes(
+        key_secret: &KeySecret,
+        nonce: &[u8; NONCE_LENGTH],
+        ciphertext: &[u8],
+    ) -> anyhow::Result<Vec<u8>> {
+        Self::encrypt_bytes(key_secret, nonce, ciphertext)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// A deterministic provider hashing differently from [`NativeCrypto`], to catch code that hashes natively regardless of its provider.
+    ///
+    /// Signing, sealing and encryption are left to [`NativeCrypto`], as their formats are fixed.
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub(crate) struct FakeCrypto;
+
+    /// Hashes everything added to it at once when digested.
+    #[derive(Clone, Debug, Default)]
+    pub(crate) struct FakeHasher(Vec<u8>);
+
+    impl CryptoProvider for FakeCrypto {
+        type StreamingHasher = FakeHasher;
+
+        fn hash_bytes(bytes: &[u8]) -> Hash {
+            Hash(blake3::derive_key("jazz-rs fake crypto", bytes))
+        }
+
+        fn streaming_hash_update(hasher: &mut FakeHasher, bytes: &[u8]) {
+            hasher.0.extend_from_slice(bytes);
+        }
+
+        fn streaming_hash_digest(hasher: &FakeHasher) -> Hash {
+            Self::hash_bytes(&hasher.0)
+        }
+
+        fn sign_bytes(signer_secret: &SignerSecret, message: &[u8]) -> Signature {
+            NativeCrypto::sign_bytes(signer_secret, message)
+        }
+
+        fn verify_bytes(
+            signer_id: &SignerID,
+            message: &[u8],
+            signature: &Signature,
+        ) -> anyhow::Result<()> {
+            NativeCrypto::verify_bytes(signer_id, message, signature)
+        }
+
+        fn seal_bytes(
+            from: &SealerSecret,
+            to: &SealerID,
+            nonce: &[u8; NONCE_LENGTH],
+            plaintext: &[u8],
+        ) -> anyhow::Result<Vec<u8>> {
+            NativeCrypto::seal_bytes(from, to, nonce, plaintext)
+        }
+
+        fn unseal_bytes(
+            sealer_secret: &SealerSecret,
+            from: &SealerID,
+            nonce: &[u8; NONCE_LENGTH],
+            ciphertext: &[u8],
+        ) -> anyhow::Result<Vec<u8>> {
+            NativeCrypto::unseal_bytes(sealer_secret, from, nonce, ciphertext)
+        }
+
+        fn encrypt_bytes(
+            key_secret: &KeySecret,
+            nonce: &[u8; NONCE_LENGTH],
+            plaintext: &[u8],
+        ) -> anyhow::Result<Vec<u8>> {
+            NativeCrypto::encrypt_bytes(key_secret, nonce, plaintext)
+        }
+
+        fn decrypt_bytes(
+            key_secret: &KeySecret,
+            nonce: &[u8; NONCE_LENGTH],
+            ciphertext: &[u8],
+        ) -> anyhow::Result<Vec<u8>> {
+            NativeCrypto::decrypt_bytes(key_secret, nonce, ciphertext)
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use super::encrypt::NonceMaterial;
+use super::provider::{CryptoProvider, NativeCrypto};
+use crate::id::sealer_id::SealerID;
+use rand_core::{OsRng, RngCore};
+use std::str::FromStr;
+use subtle::ConstantTimeEq;
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+pub const SEALER_SECRET_LENGTH: usize = crypto_box::KEY_SIZE;
+
+/// The secret half of an X25519 key pair, wiped from memory when dropped.
+///
+/// Sealer secrets are neither serialised nor displayed; their string form is only available through [`SealerSecret::expose_secret`].
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct SealerSecret([u8; SEALER_SECRET_LENGTH]);
+impl SealerSecret {
+    pub fn new(bytes: [u8; SEALER_SECRET_LENGTH]) -> Self {
+        Self(bytes)
+    }
+
+    pub fn new_random() -> Self {
+        let mut sealer_secret = Self([0u8; SEALER_SECRET_LENGTH]);
+        OsRng.fill_bytes(&mut sealer_secret.0);
+        sealer_secret
+    }
+
+    /// The raw bytes of the secret; take care not to copy or log them.
+    pub fn expose_bytes(&self) -> &[u8; SEALER_SECRET_LENGTH] {
+        &self.0
+    }
+
+    /// The secret as a string beginning with `sealerSecret_z`, as cojson writes it; take care not to copy or log it.
+    pub fn expose_secret(&self) -> Zeroizing<String> {
+        Zeroizing::new(format!(
+            "sealerSecret_z{}",
+            bs58::encode(&self.0).into_string()
+        ))
+    }
+
+    pub fn sealer_id(&self) -> SealerID {
+        SealerID::from(crypto_box::SecretKey::from_bytes(self.0).public_key())
+    }
+
+    /// Encrypts a message so that only the holder of the recipient's sealer secret can read it.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The message to seal.
+    ///
+    /// * `to` - The sealer ID of the recipient.
+    ///
+    /// * `nonce_material` - The ID of the [`CoValue`] and transaction the sealed message belongs to.
+    ///
+    /// # Returns
+    ///
+    /// The sealed message, which can only be unsealed by the recipient using the sender's sealer ID.
+    pub fn seal(
+        &self,
+        message: impl Into<serde_json::value::Value>,
+        to: &SealerID,
+        nonce_material: &NonceMaterial,
+    ) -> anyhow::Result<Vec<u8>> {
+        NativeCrypto::seal(self, to, &message.into(), nonce_material)
+    }
+
+    /// Decrypts a message sealed for the holder of this sealer secret.
+    ///
+    /// # Arguments
+    ///
+    /// * `sealed` - The sealed message.
+    ///
+    /// * `from` - The sealer ID of the sender.
+    ///
+    /// * `nonce_material` - The ID of the [`CoValue`] and transaction the sealed message belongs to.
+    ///
+    /// # Returns
+    ///
+    /// The message, if it was sealed by the holder of `from` for the given [`CoValue`] and transaction.
+    pub fn unseal(
+        &self,
+        sealed: &[u8],
+        from: &SealerID,
+        nonce_material: &NonceMaterial,
+    ) -> anyhow::Result<serde_json::value::Value> {
+        NativeCrypto::unseal(self, from, sealed, nonce_material)
+    }
+}
+
+impl std::fmt::Debug for SealerSecret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SealerSecret([REDACTED])")
+    }
+}
+
+impl FromStr for SealerSecret {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        s.split_once("sealerSecret_z").and_then(|(_, y)| {
+            bs58::decode(y).into_vec().ok().and_then(|z| z.try_into().map(Self).ok())
+        }).ok_or(anyhow::anyhow!("String not a valid sealer secret; sealer secrets begin with `sealerSecret_z` followed by a Base58-encoded X25519 secret key"))
+    }
+}
+
+impl ConstantTimeEq for SealerSecret {
+    fn ct_eq(&self, other: &Self) -> subtle::Choice {
+        self.0.ct_eq(&other.0)
+    }
+}
+
+impl PartialEq for SealerSecret {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl Eq for SealerSecret {}
+
+impl From<&SealerSecret> for SealerID {
+    fn from(sealer_secret: &SealerSecret) -> SealerID {
+        sealer_secret.sealer_id()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        crypto::{agent::AgentSecret, short_hash::SHORT_HASH_LENGTH},
+        id::{common::TransactionID, rawcoid::RawCoID, session_id::SessionID},
+    };
+    use serde_json::json;
+
+    fn nonce_material(id: u8, tx_index: usize) -> NonceMaterial {
+        NonceMaterial::new(
+            RawCoID::new([id; SHORT_HASH_LENGTH]),
+            TransactionID::new(
+                SessionID::new(AgentSecret::new_random().agent_id(), "1".into()),
+                tx_index,
+            ),
+        )
+    }
+
+    #[test]
+    fn round_trips_message() {
+        let (sender, recipient) = (SealerSecret::new_random(), SealerSecret::new_random());
+        let nonce_material = nonce_material(1, 0);
+        let sealed = sender
+            .seal(
+                json!({"secret": "key"}),
+                &recipient.sealer_id(),
+                &nonce_material,
+            )
+            .unwrap();
+        assert_eq!(
+            recipient
+                .unseal(&sealed, &sender.sealer_id(), &nonce_material)
+                .unwrap(),
+            json!({"secret": "key"})
+        );
+    }
+
+    #[test]
+    fn cannot_unseal_message_from_another_sender() {
+        let (sender, recipient) = (SealerSecret::new_random(), SealerSecret::new_random());
+        let nonce_material = nonce_material(1, 0);
+        let sealed = sender
+            .seal(json!("secret"), &recipient.sealer_id(), &nonce_material)
+            .unwrap();
+        let other_sender = SealerSecret::new_random().sealer_id();
+        assert!(
+            recipient
+                .unseal(&sealed, &other_sender, &nonce_material)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn cannot_unseal_message_for_another_covalue_or_transaction() {
+        let (sender, recipient) = (SealerSecret::new_random(), SealerSecret::new_random());
+        let nonce_material = nonce_material(1, 0);
+        let sealed = sender
+            .seal(json!("secret"), &recipient.sealer_id(), &nonce_material)
+            .unwrap();
+        let other_covalue = NonceMaterial::new(
+            RawCoID::new([2; SHORT_HASH_LENGTH]),
+            nonce_material.tx.clone(),
+        );
+        let other_transaction = NonceMaterial::new(
+            nonce_material.in_,
+            TransactionID::new(nonce_material.tx.session_id().clone(), 1),
+        );
+        for other in [other_covalue, other_transaction] {
+            assert!(
+                recipient
+                    .unseal(&sealed, &sender.sealer_id(), &other)
+                    .is_err()
+            );
+        }
+    }
+}
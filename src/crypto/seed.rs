@@ -0,0 +1,190 @@
+use super::{agent::AgentSecret, seal::SealerSecret, sign::SignerSecret};
+use bip39::{Language, Mnemonic};
+use ed25519_dalek::SigningKey;
+use rand_core::{OsRng, RngCore};
+use subtle::ConstantTimeEq;
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+pub const SECRET_SEED_LENGTH: usize = 32;
+
+/// Secret material from which an agent's sealer and signer secrets are deterministically derived, wiped from memory when dropped.
+///
+/// Secret seeds are neither serialised nor displayed; they are only written out as a mnemonic phrase with [`SecretSeed::to_mnemonic`].
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct SecretSeed([u8; SECRET_SEED_LENGTH]);
+impl SecretSeed {
+    pub fn new(bytes: [u8; SECRET_SEED_LENGTH]) -> Self {
+        Self(bytes)
+    }
+
+    pub fn new_random() -> Self {
+        let mut secret_seed = Self([0u8; SECRET_SEED_LENGTH]);
+        OsRng.fill_bytes(&mut secret_seed.0);
+        secret_seed
+    }
+
+    /// The raw bytes of the seed; take care not to copy or log them.
+    pub fn expose_bytes(&self) -> &[u8; SECRET_SEED_LENGTH] {
+        &self.0
+    }
+
+    /// Reads a secret seed back from its mnemonic phrase, checking the phrase's checksum.
+    ///
+    /// The phrase is read regardless of case and of the whitespace between its words, as it may have been copied by hand.
+    pub fn from_mnemonic(phrase: &str) -> anyhow::Result<Self> {
+        let mut normalized = Zeroizing::new(String::with_capacity(phrase.len()));
+        for word in phrase.split_whitespace() {
+            if !normalized.is_empty() {
+                normalized.push(' ');
+            }
+            normalized.extend(word.chars().flat_map(char::to_lowercase));
+        }
+        let mnemonic = Mnemonic::parse_in_normalized(Language::English, &normalized)
+            .map_err(|e| anyhow::anyhow!("String not a valid mnemonic phrase: {e}"))?;
+        Self::try_from(Zeroizing::new(mnemonic.to_entropy()).as_slice()).map_err(|_| {
+            anyhow::anyhow!(
+                "Mnemonic phrase has {} words; secret seeds are backed up as 24-word phrases",
+                mnemonic.word_count()
+            )
+        })
+    }
+
+    /// Encodes the seed as a BIP39 mnemonic phrase of 24 English words, for backing it up by hand.
+    pub fn to_mnemonic(&self) -> Zeroizing<String> {
+        Zeroizing::new(
+            Mnemonic::from_entropy_in(Language::English, &self.0)
+                .expect("Secret seeds should be a valid length of BIP39 entropy")
+                .to_string(),
+        )
+    }
+
+    /// Derives a key from the seed with BLAKE3 in key derivation mode, as cojson does, using a context string to separate keys derived for different purposes.
+    fn derive(&self, context: &str) -> [u8; blake3::OUT_LEN] {
+        blake3::derive_key(context, &self.0)
+    }
+
+    pub fn sealer_secret(&self) -> SealerSecret {
+        SealerSecret::new(self.derive("seal"))
+    }
+
+    pub fn signer_secret(&self) -> SignerSecret {
+        let mut bytes = self.derive("sign");
+        let signing_key = SigningKey::from_bytes(&bytes);
+        bytes.zeroize();
+        SignerSecret::new(signing_key)
+    }
+
+    pub fn agent_secret(&self) -> AgentSecret {
+        AgentSecret::new(self.sealer_secret(), self.signer_secret())
+    }
+}
+
+impl std::fmt::Debug for SecretSeed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SecretSeed([REDACTED])")
+    }
+}
+
+impl ConstantTimeEq for SecretSeed {
+    fn ct_eq(&self, other: &Self) -> subtle::Choice {
+        self.0.ct_eq(&other.0)
+    }
+}
+
+impl PartialEq for SecretSeed {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl Eq for SecretSeed {}
+
+impl TryFrom<&[u8]> for SecretSeed {
+    type Error = anyhow::Error;
+
+    fn try_from(bytes: &[u8]) -> anyhow::Result<Self> {
+        bytes.try_into().map(Self).map_err(|_| {
+            anyhow::anyhow!(
+                "Secret seeds must be exactly {SECRET_SEED_LENGTH} bytes long, got {} bytes",
+                bytes.len()
+            )
+        })
+    }
+}
+
+impl From<&SecretSeed> for AgentSecret {
+    fn from(secret_seed: &SecretSeed) -> AgentSecret {
+        secret_seed.agent_secret()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_mnemonic_regardless_of_case_and_whitespace() {
+        let secret_seed = SecretSeed::new_random();
+        let phrase = secret_seed.to_mnemonic();
+        let copied_by_hand = format!(
+            "  {}\n",
+            phrase
+                .split(' ')
+                .enumerate()
+                .map(|(i, word)| match i % 3 {
+                    0 => word.to_uppercase(),
+                    1 => format!("{word}\t"),
+                    _ => word.to_owned(),
+                })
+                .collect::<Vec<_>>()
+                .join("  ")
+        );
+        assert!(SecretSeed::from_mnemonic(&copied_by_hand).unwrap() == secret_seed);
+    }
+
+    #[test]
+    fn rejects_mnemonic_with_bad_checksum() {
+        let phrase = SecretSeed::new([0; SECRET_SEED_LENGTH]).to_mnemonic();
+        assert_eq!(*phrase, format!("{}art", "abandon ".repeat(23)));
+        assert!(SecretSeed::from_mnemonic(&"abandon ".repeat(24)).is_err());
+    }
+
+    #[test]
+    fn derives_keys_in_blake3_key_derivation_mode() {
+        let secret_seed = SecretSeed::new([7; SECRET_SEED_LENGTH]);
+        assert_eq!(
+            secret_seed.sealer_secret().expose_bytes(),
+            &blake3::derive_key("seal", &[7; SECRET_SEED_LENGTH])
+        );
+        assert_eq!(
+            secret_seed.signer_secret().verifying_key(),
+            SigningKey::from_bytes(&blake3::derive_key("sign", &[7; SECRET_SEED_LENGTH]))
+                .verifying_key()
+        );
+    }
+
+    #[test]
+    fn derives_the_same_agent_from_the_same_seed() {
+        let agent_secret = SecretSeed::new([0; SECRET_SEED_LENGTH]).agent_secret();
+        assert_eq!(
+            agent_secret.sealer_id().to_string(),
+            "sealer_z986LtSirZ41aoxD8ief849VBNhaZCqJiVEj3JET2tJmC"
+        );
+        assert_eq!(
+            agent_secret.signer_id().to_string(),
+            "signer_zCTkFZobZgbFMuP4vDz234J9nbsxg2RVN2MB48171RBoC"
+        );
+        let secret_seed = SecretSeed::new_random();
+        assert_eq!(
+            secret_seed.agent_secret().agent_id(),
+            SecretSeed::try_from(secret_seed.expose_bytes().as_slice())
+                .unwrap()
+                .agent_secret()
+                .agent_id()
+        );
+        assert_ne!(
+            secret_seed.agent_secret().agent_id(),
+            SecretSeed::new_random().agent_secret().agent_id()
+        );
+    }
+}
@@ -1,18 +1,16 @@
+use super::provider::{CryptoProvider, NativeCrypto};
+use serde::{Deserialize, Serialize};
 use std::{fmt::Display, str::FromStr};
 
 pub const SHORT_HASH_LENGTH: usize = 19;
 
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+#[serde(into = "String", try_from = "String")]
 pub struct ShortHash(pub(crate) [u8; SHORT_HASH_LENGTH]);
 
 impl ShortHash {
     pub fn new<T: Into<serde_json::value::Value>>(value: T) -> Self {
-        let json_value = value.into();
-        let json_string = format!("{:#}", json_value);
-        Self(
-            blake3::hash(json_string.as_bytes()).as_bytes()[..SHORT_HASH_LENGTH]
-                .try_into()
-                .expect("Array should have `SHORT_HASH_LENGTH` bytes"),
-        )
+        NativeCrypto::short_hash(&value.into())
     }
 }
 
@@ -41,3 +39,17 @@ impl From<super::hash::Hash> for ShortHash {
         )
     }
 }
+
+impl From<ShortHash> for String {
+    fn from(value: ShortHash) -> String {
+        value.to_string()
+    }
+}
+
+impl TryFrom<String> for ShortHash {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> anyhow::Result<Self> {
+        value.parse()
+    }
+}
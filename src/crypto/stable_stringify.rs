@@ -0,0 +1,189 @@
+use serde_json::value::Value;
+
+/// Serialises a JSON value in the canonical form used for hashing, signing, and encryption.
+///
+/// The output matches `stableStringify` from the TypeScript implementation of Jazz byte-for-byte:
+/// object keys are sorted in UTF-16 code unit order (as JavaScript's `Array.prototype.sort` does),
+/// no whitespace is emitted, and numbers are formatted as JavaScript's `Number.prototype.toString` would.
+pub fn stable_stringify(value: &Value) -> String {
+    let mut output = String::new();
+    write_value(value, &mut output);
+    output
+}
+
+fn write_value(value: &Value, output: &mut String) {
+    match value {
+        Value::Null => output.push_str("null"),
+        Value::Bool(boolean) => output.push_str(if *boolean { "true" } else { "false" }),
+        Value::Number(number) => match (number.as_u64(), number.as_i64(), number.as_f64()) {
+            (Some(unsigned), _, _) => output.push_str(&unsigned.to_string()),
+            (_, Some(signed), _) => output.push_str(&signed.to_string()),
+            (_, _, Some(float)) => output.push_str(&format_js_number(float)),
+            _ => output.push_str("null"),
+        },
+        Value::String(string) => write_string(string, output),
+        Value::Array(array) => {
+            output.push('[');
+            for (index, item) in array.iter().enumerate() {
+                if index > 0 {
+                    output.push(',');
+                }
+                write_value(item, output);
+            }
+            output.push(']');
+        }
+        Value::Object(object) => {
+            let mut entries: Vec<_> = object.iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+            output.push('{');
+            for (index, (key, item)) in entries.into_iter().enumerate() {
+                if index > 0 {
+                    output.push(',');
+                }
+                write_string(key, output);
+                output.push(':');
+                write_value(item, output);
+            }
+            output.push('}');
+        }
+    }
+}
+
+fn write_string(string: &str, output: &mut String) {
+    output.push('"');
+    for character in string.chars() {
+        match character {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\u{08}' => output.push_str("\\b"),
+            '\u{0C}' => output.push_str("\\f"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            control if control < '\u{20}' => {
+                output.push_str(&format!("\\u{:04x}", control as u32));
+            }
+            _ => output.push(character),
+        }
+    }
+    output.push('"');
+}
+
+/// Formats a floating-point number the way JavaScript's `Number.prototype.toString` does.
+fn format_js_number(number: f64) -> String {
+    if !number.is_finite() {
+        // `JSON.stringify` serialises non-finite numbers as `null`.
+        return "null".to_owned();
+    }
+    if number == 0.0 {
+        return "0".to_owned();
+    }
+    let magnitude = number.abs();
+    if (1e-6..1e21).contains(&magnitude) {
+        format!("{number}")
+    } else {
+        let exponential = format!("{number:e}");
+        match exponential.split_once('e') {
+            Some((mantissa, exponent)) if !exponent.starts_with('-') => {
+                format!("{mantissa}e+{exponent}")
+            }
+            _ => exponential,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::stable_stringify;
+    use serde_json::{Value, json};
+
+    /// Checks a value against the output of cojson's `stableStringify` for the same value.
+    fn check(value: Value, expected: &str) {
+        assert_eq!(stable_stringify(&value), expected);
+    }
+
+    #[test]
+    fn sorts_nested_keys() {
+        check(
+            json!({"b": {"z": 1, "a": [{"y": true, "x": null}]}, "a": "", "B": false}),
+            r#"{"B":false,"a":"","b":{"a":[{"x":null,"y":true}],"z":1}}"#,
+        );
+        // `😀` is a surrogate pair starting at 0xD83D, so it sorts before 0xFF61 in UTF-16, unlike in UTF-8.
+        check(
+            json!({"\u{FF61}": 1, "\u{1F600}": 2, "é": 3, "z": 4}),
+            "{\"z\":4,\"é\":3,\"\u{1F600}\":2,\"\u{FF61}\":1}",
+        );
+    }
+
+    #[test]
+    fn writes_arrays() {
+        check(json!([]), "[]");
+        check(json!({}), "{}");
+        check(
+            json!([1, "a", [null, []], {"k": []}]),
+            r#"[1,"a",[null,[]],{"k":[]}]"#,
+        );
+    }
+
+    #[test]
+    fn escapes_strings() {
+        check(
+            json!("quote \" backslash \\ slash /"),
+            r#""quote \" backslash \\ slash /""#,
+        );
+        check(
+            json!("\u{08}\u{0C}\n\r\t\u{00}\u{01}\u{1F}\u{7F}"),
+            "\"\\b\\f\\n\\r\\t\\u0000\\u0001\\u001f\u{7F}\"",
+        );
+        // `JSON.stringify` leaves non-ASCII characters, including the line and paragraph separators, unescaped.
+        check(
+            json!("é 日本 \u{1F600} \u{2028}\u{2029}"),
+            "\"é 日本 \u{1F600} \u{2028}\u{2029}\"",
+        );
+    }
+
+    #[test]
+    fn formats_numbers() {
+        let cases = [
+            (0.0, "0"),
+            (-0.0, "0"),
+            (42.0, "42"),
+            (-42.0, "-42"),
+            (9007199254740991.0, "9007199254740991"),
+            (1.5, "1.5"),
+            (-0.1, "-0.1"),
+            (0.1, "0.1"),
+            (100.0, "100"),
+            (1e20, "100000000000000000000"),
+            (123456789012345680000.0, "123456789012345680000"),
+            (1e21, "1e+21"),
+            (1.5e21, "1.5e+21"),
+            (-1e21, "-1e+21"),
+            (1e300, "1e+300"),
+            (0.000001, "0.000001"),
+            (0.0000001, "1e-7"),
+            (1.5e-7, "1.5e-7"),
+            (5e-324, "5e-324"),
+            (f64::MAX, "1.7976931348623157e+308"),
+        ];
+        for (number, expected) in cases {
+            assert_eq!(
+                stable_stringify(&Value::from(number)),
+                expected,
+                "formatting {number:e}"
+            );
+        }
+        check(json!(42), "42");
+        check(json!(-42), "-42");
+        check(serde_json::from_str("-0").unwrap(), "0");
+        check(json!(u32::MAX), "4294967295");
+    }
+
+    #[test]
+    fn writes_transaction_changes() {
+        check(
+            json!([{"op": "set", "key": "color", "value": 0.5}, {"op": "del", "key": "size"}]),
+            r#"[{"key":"color","op":"set","value":0.5},{"key":"size","op":"del"}]"#,
+        );
+    }
+}
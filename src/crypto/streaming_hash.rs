@@ -1,20 +1,20 @@
+use super::provider::{CryptoProvider, NativeCrypto};
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Clone, Debug, Default)]
 #[serde(rename_all = "camelCase")]
-pub struct StreamingHash {
+#[serde(bound = "")]
+pub struct StreamingHash<C: CryptoProvider = NativeCrypto> {
     #[serde(skip)]
-    hasher: blake3::Hasher,
+    hasher: C::StreamingHasher,
 }
 
-impl StreamingHash {
+impl<C: CryptoProvider> StreamingHash<C> {
     pub fn update<T: Into<serde_json::value::Value>>(&mut self, value: T) {
-        let json_value = value.into();
-        let json_string = format!("{:#}", json_value);
-        self.hasher.update_rayon(json_string.as_bytes());
+        C::streaming_hash(&mut self.hasher, &value.into());
     }
 
     pub fn digest(&self) -> super::hash::Hash {
-        super::hash::Hash(*self.hasher.finalize().as_bytes())
+        C::streaming_hash_digest(&self.hasher)
     }
 }
@@ -0,0 +1,67 @@
+use super::{sealer_id::SealerID, signer_id::SignerID};
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, str::FromStr};
+
+/// The public identity of an agent, able to both verify signatures and receive sealed messages.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[serde(into = "String", try_from = "String")]
+pub struct AgentID {
+    sealer_id: SealerID,
+    signer_id: SignerID,
+}
+impl AgentID {
+    pub fn new(sealer_id: SealerID, signer_id: SignerID) -> Self {
+        Self {
+            sealer_id,
+            signer_id,
+        }
+    }
+    pub fn sealer_id(&self) -> &SealerID {
+        &self.sealer_id
+    }
+    pub fn signer_id(&self) -> &SignerID {
+        &self.signer_id
+    }
+}
+
+impl FromStr for AgentID {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        s.split_once('/').and_then(|(x, y)| {
+            SealerID::from_str(x).ok().zip(SignerID::from_str(y).ok()).map(|(sealer_id, signer_id)| Self::new(sealer_id, signer_id))
+        }).ok_or(anyhow::anyhow!("String not a valid agent ID; agent IDs consist of a sealer ID followed by `/` followed by a signer ID"))
+    }
+}
+
+impl Display for AgentID {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.sealer_id, self.signer_id)
+    }
+}
+
+impl From<&AgentID> for SealerID {
+    fn from(agent_id: &AgentID) -> SealerID {
+        agent_id.sealer_id
+    }
+}
+
+impl From<&AgentID> for SignerID {
+    fn from(agent_id: &AgentID) -> SignerID {
+        agent_id.signer_id.clone()
+    }
+}
+
+impl From<AgentID> for String {
+    fn from(value: AgentID) -> String {
+        value.to_string()
+    }
+}
+
+impl TryFrom<String> for AgentID {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> anyhow::Result<Self> {
+        value.parse()
+    }
+}
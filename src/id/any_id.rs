@@ -0,0 +1,225 @@
+use super::{
+    agent_id::AgentID,
+    common::TransactionID,
+    key_id::KeyID,
+    rawcoid::RawCoID,
+    sealer_id::SealerID,
+    session_id::{SessionID, SessionOwner},
+    signer_id::SignerID,
+};
+use crate::crypto::{hash::Hash, short_hash::ShortHash, sign::Signature};
+use std::{fmt::Display, str::FromStr};
+
+/// Any of the prefixed identifiers, hashes, and signatures used by Jazz, classified by its form.
+///
+/// Secrets, such as `keySecret_z…` or an agent's `sealerSecret_z…/signerSecret_z…`, fail to parse rather than being classified, so that tools printing identifiers do not print them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AnyJazzId {
+    /// `co_z…`
+    CoID(RawCoID),
+    /// `co_z…_session_z…` or `sealer_z…/signer_z…_session_z…`
+    Session(SessionID),
+    /// `co_z…_session_z…:<index>`
+    Transaction(TransactionID),
+    /// `sealer_z…/signer_z…`
+    Agent(AgentID),
+    /// `signer_z…`
+    Signer(SignerID),
+    /// `sealer_z…`
+    Sealer(SealerID),
+    /// `key_z…`
+    Key(KeyID),
+    /// `hash_z…`
+    Hash(Hash),
+    /// `shortHash_z…`
+    ShortHash(ShortHash),
+    /// `signature_z…`
+    Signature(Signature),
+}
+
+impl AnyJazzId {
+    /// A name for the kind of identifier, for printing.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AnyJazzId::CoID(_) => "CoID",
+            AnyJazzId::Session(_) => "session ID",
+            AnyJazzId::Transaction(_) => "transaction ID",
+            AnyJazzId::Agent(_) => "agent ID",
+            AnyJazzId::Signer(_) => "signer ID",
+            AnyJazzId::Sealer(_) => "sealer ID",
+            AnyJazzId::Key(_) => "key ID",
+            AnyJazzId::Hash(_) => "hash",
+            AnyJazzId::ShortHash(_) => "short hash",
+            AnyJazzId::Signature(_) => "signature",
+        }
+    }
+
+    /// The identifiers nested inside this one, eg. the account ID a session belongs to.
+    pub fn parts(&self) -> Vec<AnyJazzId> {
+        match self {
+            AnyJazzId::Session(session_id) => vec![match session_id.owner() {
+                SessionOwner::Account(raw_account_id) => {
+                    AnyJazzId::CoID(raw_account_id.raw().clone())
+                }
+                SessionOwner::Agent(agent_id) => AnyJazzId::Agent(agent_id.as_ref().clone()),
+            }],
+            AnyJazzId::Transaction(transaction_id) => {
+                vec![AnyJazzId::Session(transaction_id.session_id().clone())]
+            }
+            AnyJazzId::Agent(agent_id) => vec![
+                AnyJazzId::Sealer(*agent_id.sealer_id()),
+                AnyJazzId::Signer(agent_id.signer_id().clone()),
+            ],
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl FromStr for AnyJazzId {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        if s.starts_with("keySecret_z")
+            || s.starts_with("sealerSecret_z")
+            || s.starts_with("signerSecret_z")
+        {
+            return Err(anyhow::anyhow!(
+                "String is a secret, not an identifier; secrets are not parsed as identifiers"
+            ));
+        }
+        Ok(match s {
+            _ if s.contains("_session_z") && s.contains(':') => AnyJazzId::Transaction(s.parse()?),
+            _ if s.contains("_session_z") => AnyJazzId::Session(s.parse()?),
+            _ if s.contains('/') => AnyJazzId::Agent(s.parse()?),
+            _ if s.starts_with("co_z") => AnyJazzId::CoID(s.parse()?),
+            _ if s.starts_with("signer_z") => AnyJazzId::Signer(s.parse()?),
+            _ if s.starts_with("sealer_z") => AnyJazzId::Sealer(s.parse()?),
+            _ if s.starts_with("key_z") => AnyJazzId::Key(s.parse()?),
+            _ if s.starts_with("shortHash_z") => AnyJazzId::ShortHash(s.parse()?),
+            _ if s.starts_with("hash_z") => AnyJazzId::Hash(s.parse()?),
+            _ if s.starts_with("signature_z") => AnyJazzId::Signature(s.parse()?),
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "String not a known Jazz identifier; expected a CoID, session ID, transaction ID, agent ID, signer ID, sealer ID, key ID, hash, short hash, or signature"
+                ));
+            }
+        })
+    }
+}
+
+impl Display for AnyJazzId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AnyJazzId::CoID(x) => x.fmt(f),
+            AnyJazzId::Session(x) => x.fmt(f),
+            AnyJazzId::Transaction(x) => x.fmt(f),
+            AnyJazzId::Agent(x) => x.fmt(f),
+            AnyJazzId::Signer(x) => x.fmt(f),
+            AnyJazzId::Sealer(x) => x.fmt(f),
+            AnyJazzId::Key(x) => x.fmt(f),
+            AnyJazzId::Hash(x) => x.fmt(f),
+            AnyJazzId::ShortHash(x) => x.fmt(f),
+            AnyJazzId::Signature(x) => x.fmt(f),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        crypto::{
+            agent::AgentSecret,
+            encrypt::KeySecret,
+            provider::{CryptoProvider, NativeCrypto},
+        },
+        id::common::RawAccountID,
+    };
+
+    #[test]
+    fn classifies_every_kind_of_identifier() {
+        let agent_secret = AgentSecret::new_random();
+        let agent_id = agent_secret.agent_id();
+        let raw_co_id = RawCoID::new([1; 19]);
+        let session_id = SessionID::new_random(RawAccountID::new(raw_co_id.clone()));
+        let hash = NativeCrypto::hash(&"hello".into());
+        let ids = [
+            AnyJazzId::CoID(raw_co_id),
+            AnyJazzId::Session(session_id.clone()),
+            AnyJazzId::Session(SessionID::new_random(agent_id.clone())),
+            AnyJazzId::Transaction(TransactionID::new(session_id, 12)),
+            AnyJazzId::Agent(agent_id.clone()),
+            AnyJazzId::Signer(agent_id.signer_id().clone()),
+            AnyJazzId::Sealer(*agent_id.sealer_id()),
+            AnyJazzId::Key(KeyID::new_random()),
+            AnyJazzId::Hash(hash),
+            AnyJazzId::ShortHash(ShortHash::from(hash)),
+            AnyJazzId::Signature(NativeCrypto::sign(
+                agent_secret.signer_secret(),
+                &"hello".into(),
+            )),
+        ];
+        for id in ids {
+            let string = id.to_string();
+            assert_eq!(string.parse::<AnyJazzId>().unwrap(), id, "{string}");
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_and_malformed_strings() {
+        for string in [
+            "",
+            "hello",
+            "co_",
+            "co_z0OIl",
+            "signer_z",
+            "hash_zabc",
+            "key_z1",
+            "sealer_z1/signer_z1",
+            "co_z1_session_z1:x",
+            "unknown_z3yZe7d",
+        ] {
+            assert!(string.parse::<AnyJazzId>().is_err(), "{string}");
+        }
+    }
+
+    #[test]
+    fn rejects_secrets() {
+        let agent_secret = AgentSecret::new_random();
+        for secret in [
+            KeySecret::new_random().0.expose_secret(),
+            agent_secret.sealer_secret().expose_secret(),
+            agent_secret.signer_secret().expose_secret(),
+            agent_secret.expose_secret(),
+        ] {
+            let error = secret.parse::<AnyJazzId>().unwrap_err();
+            assert!(error.to_string().contains("is a secret"));
+        }
+    }
+
+    #[test]
+    fn finds_the_owner_of_a_session() {
+        let agent_id = AgentSecret::new_random().agent_id();
+        let raw_co_id = RawCoID::new([1; 19]);
+        let account_session = SessionID::new_random(RawAccountID::new(raw_co_id.clone()));
+        assert_eq!(
+            AnyJazzId::Session(account_session.clone()).parts(),
+            [AnyJazzId::CoID(raw_co_id)]
+        );
+        assert_eq!(
+            AnyJazzId::Session(SessionID::new_random(agent_id.clone())).parts(),
+            [AnyJazzId::Agent(agent_id.clone())]
+        );
+        assert_eq!(
+            AnyJazzId::Transaction(TransactionID::new(account_session.clone(), 0)).parts(),
+            [AnyJazzId::Session(account_session)]
+        );
+        assert_eq!(
+            AnyJazzId::Agent(agent_id.clone()).parts(),
+            [
+                AnyJazzId::Sealer(*agent_id.sealer_id()),
+                AnyJazzId::Signer(agent_id.signer_id().clone())
+            ]
+        );
+    }
+}
@@ -1,10 +1,14 @@
 use super::{rawcoid::RawCoID, session_id::SessionID};
-use crate::{covalue::common::RawCoValue, permission::account::Account};
+use crate::{
+    covalue::{common::RawCoValue, header::CoValueHeader},
+    crypto::provider::{CryptoProvider, NativeCrypto},
+    permission::account::Account,
+};
 use serde::{Deserialize, Serialize};
-use std::{fmt::Display, marker::PhantomData};
+use std::{cmp::Ordering, fmt::Display, marker::PhantomData, str::FromStr};
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
-#[serde(rename_all = "camelCase")]
+#[serde(transparent, bound = "")]
 pub struct CoID<T: RawCoValue>(pub(super) RawCoID, PhantomData<T>);
 impl<T: RawCoValue> CoID<T> {
     pub fn new(id: RawCoID) -> Self {
@@ -13,6 +17,34 @@ impl<T: RawCoValue> CoID<T> {
     pub fn raw(&self) -> &RawCoID {
         &self.0
     }
+
+    /// Checks that a header is that of the [`CoValue`] this ID refers to, and that it is of the expected kind.
+    ///
+    /// # Returns
+    ///
+    /// A [`HeaderMismatch`](crate::covalue::common::HeaderMismatch) error if the header is of another kind.
+    pub fn check_header(&self, header: &CoValueHeader) -> anyhow::Result<()> {
+        self.check_header_with::<NativeCrypto>(header)
+    }
+
+    /// Checks a header as [`CoID::check_header`] does, hashing it using the given [`CryptoProvider`].
+    pub fn check_header_with<C: CryptoProvider>(
+        &self,
+        header: &CoValueHeader,
+    ) -> anyhow::Result<()> {
+        let id = header.id_with::<C>()?;
+        if id != self.0 {
+            return Err(anyhow::anyhow!("Header belongs to {id}, not {}", self.0));
+        }
+        Ok(header.check_type::<T>(&self.0)?)
+    }
+
+    /// The ID of the [`CoValue`] with the given header, checking that it is of the expected kind.
+    pub fn from_header(header: &CoValueHeader) -> anyhow::Result<Self> {
+        let id = header.id()?;
+        header.check_type::<T>(&id)?;
+        Ok(Self::new(id))
+    }
 }
 
 impl<T: RawCoValue> Display for CoID<T> {
@@ -29,7 +61,55 @@ impl<T: RawCoValue> From<RawCoID> for CoID<T> {
 
 pub type RawAccountID = CoID<Account>;
 
+/// Identifies a transaction by the session it was made in and its index in that session.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
 pub struct TransactionID {
+    #[serde(rename = "sessionID")]
     session_id: SessionID,
+    #[serde(rename = "txIndex")]
     tx_index: usize,
 }
+impl TransactionID {
+    pub fn new(session_id: SessionID, tx_index: usize) -> Self {
+        Self {
+            session_id,
+            tx_index,
+        }
+    }
+    pub fn session_id(&self) -> &SessionID {
+        &self.session_id
+    }
+    pub fn tx_index(&self) -> usize {
+        self.tx_index
+    }
+}
+
+/// Transactions are ordered by the string form of their session ID, then by their index in the session, as cojson breaks ties between concurrent transactions.
+impl Ord for TransactionID {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        Ord::cmp(&self.session_id, &other.session_id)
+            .then(Ord::cmp(&self.tx_index, &other.tx_index))
+    }
+}
+
+impl PartialOrd for TransactionID {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl FromStr for TransactionID {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        s.rsplit_once(':').and_then(|(x, y)| {
+            Some(Self::new(SessionID::from_str(x).ok()?, y.parse().ok()?))
+        }).ok_or(anyhow::anyhow!("String not a valid transaction ID; transaction IDs are a session ID followed by `:` followed by the index of the transaction in the session"))
+    }
+}
+
+impl Display for TransactionID {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.session_id, self.tx_index)
+    }
+}
@@ -0,0 +1,49 @@
+use rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, str::FromStr};
+
+pub const KEY_ID_LENGTH: usize = 12;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+#[serde(into = "String", try_from = "String")]
+pub struct KeyID([u8; KEY_ID_LENGTH]);
+impl KeyID {
+    pub fn new(bytes: [u8; KEY_ID_LENGTH]) -> Self {
+        Self(bytes)
+    }
+    pub fn new_random() -> Self {
+        let mut bytes = [0u8; KEY_ID_LENGTH];
+        OsRng.fill_bytes(&mut bytes);
+        Self(bytes)
+    }
+}
+
+impl FromStr for KeyID {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        s.split_once("key_z").and_then(|(_, y)| {
+            bs58::decode(y).into_vec().ok().and_then(|z| z.try_into().map(Self).ok())
+        }).ok_or(anyhow::anyhow!("String not a valid key ID; key IDs begin with `key_z` followed by a Base58-encoded random identifier"))
+    }
+}
+
+impl Display for KeyID {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "key_z{}", bs58::encode(&self.0).into_string())
+    }
+}
+
+impl From<KeyID> for String {
+    fn from(value: KeyID) -> String {
+        value.to_string()
+    }
+}
+
+impl TryFrom<String> for KeyID {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> anyhow::Result<Self> {
+        value.parse()
+    }
+}
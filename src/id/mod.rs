@@ -1,4 +1,8 @@
+pub mod agent_id;
+pub mod any_id;
 pub mod common;
+pub mod key_id;
 pub mod rawcoid;
+pub mod sealer_id;
 pub mod session_id;
 pub mod signer_id;
@@ -8,30 +8,39 @@ use std::{fmt::Display, str::FromStr};
 use super::common::CoID;
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct RawCoID(Vec<u8>);
+#[serde(into = "String", try_from = "String")]
+pub struct RawCoID([u8; SHORT_HASH_LENGTH]);
 impl RawCoID {
-    pub fn new(bytes: Vec<u8>) -> Self {
+    pub fn new(bytes: [u8; SHORT_HASH_LENGTH]) -> Self {
         RawCoID(bytes)
     }
+    pub fn as_bytes(&self) -> &[u8; SHORT_HASH_LENGTH] {
+        &self.0
+    }
 }
 
 impl FromStr for RawCoID {
     type Err = anyhow::Error;
 
     fn from_str(s: &str) -> anyhow::Result<Self> {
-        s.split_once("co_z").and_then(|(_, y)| {
-            bs58::decode(y).into_vec().map(Self).ok()
-        }).ok_or(anyhow::anyhow!("String not a valid CoID; CoIDs begin with `co_z` followed by a Base58-encoded string"))
+        let encoded = s.strip_prefix("co_z").ok_or(anyhow::anyhow!(
+            "String not a valid CoID; CoIDs begin with `co_z` followed by a Base58-encoded short hash"
+        ))?;
+        let bytes = bs58::decode(encoded).into_vec().map_err(|e| {
+            anyhow::anyhow!("String not a valid CoID; `{encoded}` is not Base58: {e}")
+        })?;
+        let length = bytes.len();
+        bytes.try_into().map(Self).map_err(|_| {
+            anyhow::anyhow!(
+                "String not a valid CoID; CoIDs are {SHORT_HASH_LENGTH} bytes long, got {length} bytes"
+            )
+        })
     }
 }
 
 impl Display for RawCoID {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "co_z{}",
-            bs58::encode(&self.0.get(..SHORT_HASH_LENGTH).unwrap_or_default()).into_string()
-        )
+        write!(f, "co_z{}", bs58::encode(&self.0).into_string())
     }
 }
 
@@ -43,6 +52,72 @@ impl<T: RawCoValue> From<CoID<T>> for RawCoID {
 
 impl From<ShortHash> for RawCoID {
     fn from(hash: ShortHash) -> Self {
-        Self(hash.0.to_vec())
+        Self(hash.0)
+    }
+}
+
+impl From<RawCoID> for String {
+    fn from(value: RawCoID) -> String {
+        value.to_string()
+    }
+}
+
+impl TryFrom<String> for RawCoID {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> anyhow::Result<Self> {
+        value.parse()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn round_trips(bytes in any::<[u8; SHORT_HASH_LENGTH]>()) {
+            let id = RawCoID::new(bytes);
+            prop_assert_eq!(id.to_string().parse::<RawCoID>().unwrap(), id);
+        }
+
+        #[test]
+        fn rejects_wrong_length(
+            bytes in prop::collection::vec(any::<u8>(), 0..64)
+                .prop_filter("Not a CoID's length", |x| x.len() != SHORT_HASH_LENGTH)
+        ) {
+            let error = format!("co_z{}", bs58::encode(&bytes).into_string())
+                .parse::<RawCoID>()
+                .unwrap_err();
+            prop_assert_eq!(
+                error.to_string(),
+                format!(
+                    "String not a valid CoID; CoIDs are {SHORT_HASH_LENGTH} bytes long, got {} bytes",
+                    bytes.len()
+                )
+            );
+        }
+
+        #[test]
+        fn rejects_bad_prefix(
+            prefix in "[a-zA-Z_]{0,5}",
+            bytes in any::<[u8; SHORT_HASH_LENGTH]>()
+        ) {
+            let string = format!("{prefix}{}", bs58::encode(bytes).into_string());
+            prop_assume!(!string.starts_with("co_z"));
+            let error = string.parse::<RawCoID>().unwrap_err();
+            prop_assert!(
+                error.to_string().contains("CoIDs begin with `co_z`"),
+                "{}",
+                error
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_non_base58() {
+        let error = "co_z0OIl".parse::<RawCoID>().unwrap_err();
+        assert!(error.to_string().contains("is not Base58"), "{error}");
     }
 }
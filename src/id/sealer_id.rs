@@ -0,0 +1,52 @@
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, str::FromStr};
+
+pub const SEALER_ID_LENGTH: usize = crypto_box::KEY_SIZE;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+#[serde(into = "String", try_from = "String")]
+pub struct SealerID([u8; SEALER_ID_LENGTH]);
+impl SealerID {
+    pub fn new(bytes: [u8; SEALER_ID_LENGTH]) -> Self {
+        Self(bytes)
+    }
+    pub fn as_bytes(&self) -> &[u8; SEALER_ID_LENGTH] {
+        &self.0
+    }
+}
+
+impl FromStr for SealerID {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        s.split_once("sealer_z").and_then(|(_, y)| {
+            bs58::decode(y).into_vec().ok().and_then(|z| z.try_into().map(Self).ok())
+        }).ok_or(anyhow::anyhow!("String not a valid sealer ID; sealer IDs begin with `sealer_z` followed by a Base58-encoded X25519 public key"))
+    }
+}
+
+impl Display for SealerID {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "sealer_z{}", bs58::encode(&self.0).into_string())
+    }
+}
+
+impl From<crypto_box::PublicKey> for SealerID {
+    fn from(public_key: crypto_box::PublicKey) -> Self {
+        Self(public_key.to_bytes())
+    }
+}
+
+impl From<SealerID> for String {
+    fn from(value: SealerID) -> String {
+        value.to_string()
+    }
+}
+
+impl TryFrom<String> for SealerID {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> anyhow::Result<Self> {
+        value.parse()
+    }
+}
@@ -1,13 +1,124 @@
-use super::{common::RawAccountID, rawcoid::RawCoID};
+use super::{agent_id::AgentID, common::RawAccountID, rawcoid::RawCoID, signer_id::SignerID};
+use rand_core::{OsRng, RngCore};
 use serde::{Deserialize, Serialize};
-use std::{fmt::Display, str::FromStr};
+use std::{fmt::Display, str::FromStr, sync::Arc};
 
+/// Whoever makes the transactions of a session.
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
-#[serde(rename_all = "camelCase")]
-pub struct SessionID(pub(crate) RawAccountID, String);
+#[serde(into = "String", try_from = "String")]
+pub enum SessionOwner {
+    /// A session of an account, signed by the agent the account currently acts as.
+    Account(RawAccountID),
+    /// A session of an agent acting on its own behalf, such as while creating an account.
+    Agent(Box<AgentID>),
+}
+
+impl SessionOwner {
+    /// The signer expected to have signed the transactions of a session with this owner.
+    ///
+    /// # Arguments
+    ///
+    /// * `signer_for_account` - Resolves the signer an account currently acts as; only called for account sessions.
+    pub fn signer_id(
+        &self,
+        signer_for_account: impl FnOnce(&RawAccountID) -> anyhow::Result<SignerID>,
+    ) -> anyhow::Result<SignerID> {
+        match self {
+            SessionOwner::Account(raw_account_id) => signer_for_account(raw_account_id),
+            SessionOwner::Agent(agent_id) => Ok(agent_id.signer_id().clone()),
+        }
+    }
+}
+
+impl FromStr for SessionOwner {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.contains('/') {
+            true => AgentID::from_str(s).map(|x| SessionOwner::Agent(Box::new(x))),
+            false => RawCoID::from_str(s).map(|x| SessionOwner::Account(RawAccountID::from(x))),
+        }
+    }
+}
+
+impl Display for SessionOwner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SessionOwner::Account(raw_account_id) => raw_account_id.fmt(f),
+            SessionOwner::Agent(agent_id) => agent_id.fmt(f),
+        }
+    }
+}
+
+impl From<SessionOwner> for String {
+    fn from(value: SessionOwner) -> String {
+        value.to_string()
+    }
+}
+
+impl TryFrom<String> for SessionOwner {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> anyhow::Result<Self> {
+        value.parse()
+    }
+}
+
+impl From<RawAccountID> for SessionOwner {
+    fn from(raw_account_id: RawAccountID) -> Self {
+        SessionOwner::Account(raw_account_id)
+    }
+}
+
+impl From<AgentID> for SessionOwner {
+    fn from(agent_id: AgentID) -> Self {
+        SessionOwner::Agent(Box::new(agent_id))
+    }
+}
+
+/// Identifies a session by its owner and a random string.
+///
+/// The string form of the ID is kept alongside its owner, as sessions are compared by it, and are compared and cloned far more often than they are made.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[serde(into = "String", try_from = "String")]
+pub struct SessionID(pub(crate) SessionOwner, Arc<str>);
 impl SessionID {
-    pub fn new(raw_account_id: RawAccountID, random_string: String) -> Self {
-        Self(raw_account_id, random_string)
+    pub fn new(owner: impl Into<SessionOwner>, random_string: String) -> Self {
+        let owner = owner.into();
+        let string = format!("{owner}_session_z{random_string}");
+        Self(owner, string.into())
+    }
+
+    /// Whoever makes the transactions of the session.
+    pub fn owner(&self) -> &SessionOwner {
+        &self.0
+    }
+
+    /// The ID of the account the session belongs to, if it is not an agent's own session.
+    pub fn raw_account_id(&self) -> Option<&RawAccountID> {
+        match &self.0 {
+            SessionOwner::Account(raw_account_id) => Some(raw_account_id),
+            SessionOwner::Agent(_) => None,
+        }
+    }
+
+    /// The signer expected to have signed the transactions of the session.
+    ///
+    /// # Arguments
+    ///
+    /// * `signer_for_account` - Resolves the signer an account currently acts as; only called for account sessions.
+    pub fn signer_id(
+        &self,
+        signer_for_account: impl FnOnce(&RawAccountID) -> anyhow::Result<SignerID>,
+    ) -> anyhow::Result<SignerID> {
+        self.0.signer_id(signer_for_account)
+    }
+
+    /// Creates a new session for the given account or agent, with a random suffix as generated by cojson.
+    pub fn new_random(owner: impl Into<SessionOwner>) -> Self {
+        let mut bytes = [0u8; 8];
+        OsRng.fill_bytes(&mut bytes);
+        Self::new(owner, bs58::encode(bytes).into_string())
     }
 }
 
@@ -16,13 +127,98 @@ impl FromStr for SessionID {
 
     fn from_str(s: &str) -> anyhow::Result<Self> {
         s.split_once("_session_z").and_then(|(x, y)| {
-            RawCoID::from_str(x).map(|z| Self(RawAccountID::from(z), y.to_owned())).ok()
-        }).ok_or(anyhow::anyhow!("String not a valid session ID; session IDs begin with a raw account ID followed by `_session_z` followed by a random string"))
+            SessionOwner::from_str(x).map(|z| Self::new(z, y.to_owned())).ok()
+        }).ok_or(anyhow::anyhow!("String not a valid session ID; session IDs begin with a raw account ID or agent ID followed by `_session_z` followed by a random string"))
     }
 }
 
 impl Display for SessionID {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}_session_z{}", self.0, self.1)
+        f.write_str(&self.1)
+    }
+}
+
+/// Sessions are ordered by their string form, without formatting it again.
+impl Ord for SessionID {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.1.cmp(&other.1)
+    }
+}
+
+impl PartialOrd for SessionID {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl From<SessionID> for String {
+    fn from(value: SessionID) -> String {
+        value.to_string()
+    }
+}
+
+impl TryFrom<String> for SessionID {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> anyhow::Result<Self> {
+        value.parse()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{crypto::agent::AgentSecret, id::common::TransactionID};
+
+    #[test]
+    fn orders_by_string_form() {
+        let mut session_ids: Vec<SessionID> = (0..16)
+            .map(|i| match i % 2 {
+                0 => SessionID::new_random(AgentSecret::new_random().agent_id()),
+                _ => SessionID::new_random(RawAccountID::from(RawCoID::new([i; 19]))),
+            })
+            .collect();
+        session_ids.sort();
+        let strings: Vec<_> = session_ids.iter().map(SessionID::to_string).collect();
+        assert!(strings.is_sorted());
+
+        let mut tx_ids: Vec<_> = session_ids
+            .iter()
+            .rev()
+            .flat_map(|x| [2, 10, 1].map(|i| TransactionID::new(x.clone(), i)))
+            .collect();
+        tx_ids.sort();
+        let mut expected: Vec<_> = tx_ids
+            .iter()
+            .map(|x| (x.session_id().to_string(), x.tx_index()))
+            .collect();
+        expected.sort();
+        assert_eq!(
+            tx_ids
+                .iter()
+                .map(|x| (x.session_id().to_string(), x.tx_index()))
+                .collect::<Vec<_>>(),
+            expected
+        );
+    }
+
+    #[test]
+    fn parses_back_from_string_form() {
+        let session_id = SessionID::new_random(AgentSecret::new_random().agent_id());
+        let parsed: SessionID = session_id.to_string().parse().unwrap();
+        assert_eq!(parsed, session_id);
+        assert_eq!(parsed.owner(), session_id.owner());
+    }
+
+    #[test]
+    fn new_random_suffix_is_base58_of_8_bytes() {
+        let agent_id = AgentSecret::new_random().agent_id();
+        let session_id = SessionID::new_random(agent_id.clone());
+        let string = session_id.to_string();
+        let (owner, suffix) = string.split_once("_session_z").unwrap();
+        assert_eq!(owner, agent_id.to_string());
+        assert_eq!(bs58::decode(suffix).into_vec().unwrap().len(), 8);
+        assert_eq!(string.parse::<SessionID>().unwrap(), session_id);
+        assert_ne!(SessionID::new_random(agent_id), session_id);
     }
 }
@@ -1,24 +1,29 @@
+use crate::crypto::provider::{CryptoProvider, NativeCrypto};
 use crate::crypto::sign::Signature;
-use ed25519_dalek::Verifier;
 use ed25519_dalek::VerifyingKey;
+use pkcs8::{
+    Document, EncodePublicKey,
+    spki::{self, SubjectPublicKeyInfoRef},
+};
 use serde::{Deserialize, Serialize};
 use std::{fmt::Display, str::FromStr};
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[serde(into = "String", try_from = "String")]
 pub struct SignerID(VerifyingKey);
 impl SignerID {
     pub fn new(verifying_key: impl Into<VerifyingKey>) -> Self {
         SignerID(verifying_key.into())
     }
+    pub fn verifying_key(&self) -> &VerifyingKey {
+        &self.0
+    }
     pub fn verify(
         &self,
         message: impl Into<serde_json::value::Value>,
         signature: &Signature,
     ) -> anyhow::Result<()> {
-        let message_string = format!("{:#}", message.into());
-        Ok(self
-            .0
-            .verify(message_string.as_bytes(), &signature.into())?)
+        NativeCrypto::verify(self, &message.into(), signature)
     }
 }
 
@@ -27,7 +32,7 @@ impl FromStr for SignerID {
 
     fn from_str(s: &str) -> anyhow::Result<Self> {
         s.split_once("signer_z").and_then(|(_, y)| {
-            bs58::decode(y).into_vec().map(|x| x.as_slice().try_into().ok().map(|y| Self(y))).ok().flatten()
+            bs58::decode(y).into_vec().map(|x| x.as_slice().try_into().ok().map(Self)).ok().flatten()
         }).ok_or(anyhow::anyhow!("String not a valid signer ID; signer IDs begin with `signer_z` followed by a Base58-encoded verifying key"))
     }
 }
@@ -50,6 +55,65 @@ impl From<VerifyingKey> for SignerID {
 
 impl From<&VerifyingKey> for SignerID {
     fn from(verifying_key: &VerifyingKey) -> Self {
-        Self(verifying_key.clone())
+        Self(*verifying_key)
+    }
+}
+
+/// Reads a signer ID from an SPKI Ed25519 public key, giving [`pkcs8::DecodePublicKey`] for DER and PEM.
+impl TryFrom<SubjectPublicKeyInfoRef<'_>> for SignerID {
+    type Error = spki::Error;
+
+    fn try_from(public_key_info: SubjectPublicKeyInfoRef<'_>) -> spki::Result<Self> {
+        VerifyingKey::try_from(public_key_info).map(Self)
+    }
+}
+
+/// Writes a signer ID as an SPKI Ed25519 public key, in DER or PEM.
+impl EncodePublicKey for SignerID {
+    fn to_public_key_der(&self) -> spki::Result<Document> {
+        self.0.to_public_key_der()
+    }
+}
+
+impl From<SignerID> for String {
+    fn from(value: SignerID) -> String {
+        value.to_string()
+    }
+}
+
+impl TryFrom<String> for SignerID {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> anyhow::Result<Self> {
+        value.parse()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::sign::SignerSecret;
+    use pkcs8::{DecodePublicKey, LineEnding};
+
+    #[test]
+    fn round_trips_spki_der_and_pem() {
+        let signer_secret = SignerSecret::new_random();
+        let signer_id = SignerID::new(signer_secret.verifying_key());
+        let der = signer_id.to_public_key_der().unwrap();
+        assert_eq!(
+            der.as_bytes(),
+            signer_secret
+                .verifying_key()
+                .to_public_key_der()
+                .unwrap()
+                .as_bytes()
+        );
+        assert_eq!(
+            SignerID::from_public_key_der(der.as_bytes()).unwrap(),
+            signer_id
+        );
+        let pem = signer_id.to_public_key_pem(LineEnding::LF).unwrap();
+        assert!(pem.starts_with("-----BEGIN PUBLIC KEY-----"));
+        assert_eq!(SignerID::from_public_key_pem(&pem).unwrap(), signer_id);
     }
 }
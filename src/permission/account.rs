@@ -1,49 +1,236 @@
-use crate::covalue::common::RawCoValue;
-use crypto::signature::{Keypair, Signer};
-use serde::{Deserialize, Serialize};
+use crate::{
+    covalue::common::RawCoValue,
+    crypto::{
+        agent::AgentSecret,
+        provider::{CryptoProvider, NativeCrypto},
+        seal::SealerSecret,
+        seed::SecretSeed,
+        sign::{Signature, SignerSecret},
+    },
+    id::{agent_id::AgentID, sealer_id::SealerID, signer_id::SignerID},
+};
+use ed25519_dalek::VerifyingKey;
+use pkcs8::{EncodePrivateKey, PrivateKeyInfo, SecretDocument};
 use std::marker::PhantomData;
+use zeroize::Zeroizing;
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
-#[serde(rename_all = "camelCase")]
-pub struct Account<K: Signer<S> + Keypair = ed25519_dalek::SigningKey, S = ed25519_dalek::Signature>
-{
+/// An account, holding the secrets it signs and unseals with, and signing with the given [`CryptoProvider`].
+///
+/// Accounts are not serialised, as they hold secrets; store their seed's mnemonic phrase, or their signer secret in a
+/// [`Keystore`](crate::crypto::keystore::Keystore), instead.
+#[derive(Debug, Clone)]
+pub struct Account<C: CryptoProvider = NativeCrypto> {
     /// The private key of the account.
-    signing_key: K,
-    _s: PhantomData<S>,
+    signer_secret: SignerSecret,
+    /// The secret used to unseal messages sent to the account.
+    sealer_secret: Option<SealerSecret>,
+    /// The seed the account's secrets were derived from, if it was created from one.
+    secret_seed: Option<SecretSeed>,
+    _c: PhantomData<C>,
 }
 
-impl RawCoValue for Account {}
+impl RawCoValue for Account {
+    const HEADER_TYPE: &'static str = "comap";
+    const HEADER_META_TYPE: Option<&'static str> = Some("account");
+}
 
-impl std::hash::Hash for Account {
+impl<C: CryptoProvider> std::hash::Hash for Account<C> {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         self.verifying_key().hash(state)
     }
 }
 
-impl<K: Signer<S> + Keypair, S> Account<K, S> {
+impl<C: CryptoProvider> PartialEq for Account<C> {
+    fn eq(&self, other: &Self) -> bool {
+        self.signer_secret == other.signer_secret && self.sealer_secret == other.sealer_secret
+    }
+}
+
+impl<C: CryptoProvider> Eq for Account<C> {}
+
+impl<C: CryptoProvider> PartialOrd for Account<C> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Accounts are ordered field by field, comparing the public keys of their secrets rather than the secrets themselves.
+impl<C: CryptoProvider> Ord for Account<C> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (self.verifying_key().as_bytes(), self.sealer_id())
+            .cmp(&(other.verifying_key().as_bytes(), other.sealer_id()))
+    }
+}
+
+impl<C: CryptoProvider> Account<C> {
     /// Creates a new account with the given signing key.
     ///
     /// # Arguments
     ///
-    /// * `signing_key` - The private key of the account.
+    /// * `signer_secret` - The private key of the account.
     ///
     /// # Returns
     ///
     /// A new account instance.
-    pub fn new(signing_key: K) -> Self {
+    pub fn new(signer_secret: impl Into<SignerSecret>) -> Self {
         Self {
-            signing_key,
-            _s: PhantomData,
+            signer_secret: signer_secret.into(),
+            sealer_secret: None,
+            secret_seed: None,
+            _c: PhantomData,
         }
     }
 
+    /// Creates a new account with the given signing key and sealer secret.
+    ///
+    /// # Arguments
+    ///
+    /// * `signer_secret` - The private key of the account.
+    ///
+    /// * `sealer_secret` - The secret used to unseal messages sent to the account.
+    ///
+    /// # Returns
+    ///
+    /// A new account instance.
+    pub fn with_sealer_secret(
+        signer_secret: impl Into<SignerSecret>,
+        sealer_secret: SealerSecret,
+    ) -> Self {
+        Self {
+            signer_secret: signer_secret.into(),
+            sealer_secret: Some(sealer_secret),
+            secret_seed: None,
+            _c: PhantomData,
+        }
+    }
+
+    /// Creates a new account acting as the given agent.
+    pub fn from_agent_secret(agent_secret: &AgentSecret) -> Self {
+        Self::with_sealer_secret(
+            agent_secret.signer_secret().clone(),
+            agent_secret.sealer_secret().clone(),
+        )
+    }
+
+    /// Recreates an account from the secret seed it was derived from, keeping the seed so it can be backed up again.
+    pub fn from_secret_seed(secret_seed: &SecretSeed) -> Self {
+        Self {
+            secret_seed: Some(secret_seed.clone()),
+            ..Self::from_agent_secret(&secret_seed.agent_secret())
+        }
+    }
+
+    /// Recovers an account from the mnemonic phrase of the secret seed it was derived from.
+    pub fn from_mnemonic(phrase: &str) -> anyhow::Result<Self> {
+        Ok(Self::from_secret_seed(&SecretSeed::from_mnemonic(phrase)?))
+    }
+
+    /// The private key of the account.
+    pub fn signer_secret(&self) -> &SignerSecret {
+        &self.signer_secret
+    }
+
+    /// The seed the account's secrets were derived from, if it was created from one.
+    pub fn secret_seed(&self) -> Option<&SecretSeed> {
+        self.secret_seed.as_ref()
+    }
+
+    /// The mnemonic phrase of the seed the account's secrets were derived from, for backing the account up by hand.
+    ///
+    /// # Returns
+    ///
+    /// [`None`] if the account was not created from a seed, as its secrets cannot be turned back into one.
+    pub fn to_mnemonic(&self) -> Option<Zeroizing<String>> {
+        self.secret_seed.as_ref().map(SecretSeed::to_mnemonic)
+    }
+
+    /// The secret used to unseal messages sent to the account, if known.
+    pub fn sealer_secret(&self) -> Option<&SealerSecret> {
+        self.sealer_secret.as_ref()
+    }
+
+    /// The public key used to seal messages for the account, if known.
+    pub fn sealer_id(&self) -> Option<SealerID> {
+        self.sealer_secret.as_ref().map(SealerSecret::sealer_id)
+    }
+
     /// The public key of the account.
-    pub fn verifying_key(&self) -> K::VerifyingKey {
-        self.signing_key.verifying_key()
+    pub fn verifying_key(&self) -> VerifyingKey {
+        self.signer_secret.verifying_key()
+    }
+
+    /// The signer ID of the account.
+    pub fn signer_id(&self) -> SignerID {
+        SignerID::new(self.verifying_key())
+    }
+
+    /// The secret identity of the agent the account acts as, if its sealer secret is known.
+    pub fn agent_secret(&self) -> Option<AgentSecret> {
+        self.sealer_secret
+            .clone()
+            .map(|sealer_secret| AgentSecret::new(sealer_secret, self.signer_secret.clone()))
+    }
+
+    /// The public identity of the agent the account acts as, if its sealer secret is known.
+    pub fn agent_id(&self) -> Option<AgentID> {
+        self.sealer_id()
+            .map(|sealer_id| AgentID::new(sealer_id, self.signer_id()))
     }
 
     /// Signs a message with the account's private key.
-    pub fn sign(&self, message: &[u8]) -> S {
-        self.signing_key.sign(message)
+    pub fn sign(&self, message: &[u8]) -> Signature {
+        C::sign_bytes(&self.signer_secret, message)
+    }
+}
+
+/// Reads an account from a PKCS#8 Ed25519 private key; the account's sealer secret is left unknown.
+impl<C: CryptoProvider> TryFrom<PrivateKeyInfo<'_>> for Account<C> {
+    type Error = pkcs8::Error;
+
+    fn try_from(private_key_info: PrivateKeyInfo<'_>) -> pkcs8::Result<Self> {
+        SignerSecret::try_from(private_key_info).map(Self::new)
+    }
+}
+
+/// Writes the private key of an account as a PKCS#8 Ed25519 private key; the account's sealer secret is not included.
+impl<C: CryptoProvider> EncodePrivateKey for Account<C> {
+    fn to_pkcs8_der(&self) -> pkcs8::Result<SecretDocument> {
+        self.signer_secret.to_pkcs8_der()
+    }
+}
+
+impl<C: CryptoProvider> From<&AgentSecret> for Account<C> {
+    fn from(agent_secret: &AgentSecret) -> Self {
+        Self::from_agent_secret(agent_secret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pkcs8::{DecodePrivateKey, LineEnding};
+
+    #[test]
+    fn backs_up_account_created_from_seed() {
+        let account = Account::<NativeCrypto>::from_secret_seed(&SecretSeed::new_random());
+        let phrase = account.to_mnemonic().unwrap();
+        let recovered = Account::<NativeCrypto>::from_mnemonic(&phrase).unwrap();
+        assert!(recovered == account);
+        assert_eq!(recovered.to_mnemonic().unwrap(), phrase);
+    }
+
+    #[test]
+    fn round_trips_pkcs8_pem_without_sealer_secret() {
+        let account = Account::<NativeCrypto>::from_agent_secret(&AgentSecret::new_random());
+        let pem = account.to_pkcs8_pem(LineEnding::LF).unwrap();
+        let read = Account::<NativeCrypto>::from_pkcs8_pem(&pem).unwrap();
+        assert_eq!(read.signer_id(), account.signer_id());
+        assert!(read.sealer_secret().is_none());
+    }
+
+    #[test]
+    fn cannot_back_up_account_created_from_keys() {
+        let account = Account::<NativeCrypto>::from_agent_secret(&AgentSecret::new_random());
+        assert!(account.to_mnemonic().is_none());
     }
 }
@@ -1,7 +1,8 @@
 use serde::{Deserialize, Serialize};
+use std::{fmt::Display, str::FromStr};
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
-#[serde(rename_all_fields = "camelCase")]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "camelCase")]
 pub enum AccountRole {
     Reader,
     Writer,
@@ -9,16 +10,98 @@ pub enum AccountRole {
     WriteOnly,
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
-#[serde(rename_all_fields = "camelCase")]
+/// The role of a member of a group, written as cojson writes it, eg. `writer` or `readerInvite`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(into = "String", try_from = "String")]
 pub enum Role {
-    Account {
-        #[serde(flatten)]
-        role: AccountRole,
-    },
+    Account { role: AccountRole },
     Revoked,
     AdminInvite,
     WriterInvite,
     ReaderInvite,
     WriteOnlyInvite,
 }
+
+impl Role {
+    pub fn is_admin(&self) -> bool {
+        matches!(
+            self,
+            Role::Account {
+                role: AccountRole::Admin
+            }
+        )
+    }
+
+    /// Whether members with this role may make changes to [`CoValue`]s owned by the group.
+    pub fn can_write(&self) -> bool {
+        matches!(
+            self,
+            Role::Account {
+                role: AccountRole::Writer | AccountRole::Admin | AccountRole::WriteOnly
+            }
+        )
+    }
+}
+
+impl FromStr for Role {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        Ok(match s {
+            "reader" => Role::Account {
+                role: AccountRole::Reader,
+            },
+            "writer" => Role::Account {
+                role: AccountRole::Writer,
+            },
+            "admin" => Role::Account {
+                role: AccountRole::Admin,
+            },
+            "writeOnly" => Role::Account {
+                role: AccountRole::WriteOnly,
+            },
+            "revoked" => Role::Revoked,
+            "adminInvite" => Role::AdminInvite,
+            "writerInvite" => Role::WriterInvite,
+            "readerInvite" => Role::ReaderInvite,
+            "writeOnlyInvite" => Role::WriteOnlyInvite,
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "String not a valid role; roles are `reader`, `writer`, `admin`, `writeOnly`, `revoked`, or an invite such as `readerInvite`"
+                ));
+            }
+        })
+    }
+}
+
+impl Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Role::Account { role } => match role {
+                AccountRole::Reader => "reader",
+                AccountRole::Writer => "writer",
+                AccountRole::Admin => "admin",
+                AccountRole::WriteOnly => "writeOnly",
+            },
+            Role::Revoked => "revoked",
+            Role::AdminInvite => "adminInvite",
+            Role::WriterInvite => "writerInvite",
+            Role::ReaderInvite => "readerInvite",
+            Role::WriteOnlyInvite => "writeOnlyInvite",
+        })
+    }
+}
+
+impl From<Role> for String {
+    fn from(value: Role) -> String {
+        value.to_string()
+    }
+}
+
+impl TryFrom<String> for Role {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> anyhow::Result<Self> {
+        value.parse()
+    }
+}
@@ -1 +1,102 @@
+use super::common::Role;
+use crate::{
+    covalue::{comap::MapOpPayload, session::ValidTransaction},
+    id::session_id::SessionOwner,
+};
+use std::collections::HashMap;
 
+/// The key a group gives a role to everyone under, rather than to a single account or agent.
+pub const EVERYONE: &str = "everyone";
+
+/// The roles of the members of a group over time, read from the group's transactions.
+///
+/// Members are accounts, or agents acting on their own behalf, such as the agent an account was created with.
+/// A member without a role of its own has the role given to [`EVERYONE`], if any.
+///
+/// Only admins may change a group, apart from its initial admin making itself an admin.
+/// Invites are not yet supported, so changes made with an invite are not valid.
+#[derive(Debug, Clone, Default)]
+pub struct GroupRoles {
+    /// When each member was given each of its roles, from earliest to latest, keyed by the member's ID or [`EVERYONE`].
+    changes: HashMap<String, Vec<(u64, Role)>>,
+}
+
+impl GroupRoles {
+    /// Reads the roles of a group from its transactions, keeping only those made by a member allowed to make them at the time.
+    ///
+    /// # Arguments
+    ///
+    /// * `initial_admin` - The account or agent that created the group.
+    ///
+    /// * `transactions` - The transactions of the group, ordered by when they were made.
+    ///
+    /// # Returns
+    ///
+    /// The roles, and the transactions that were valid.
+    pub fn from_transactions(
+        initial_admin: &SessionOwner,
+        transactions: Vec<ValidTransaction>,
+    ) -> (Self, Vec<ValidTransaction>) {
+        let mut roles = Self::default();
+        let mut valid = Vec::new();
+        for transaction in transactions {
+            let author = transaction.tx_id.session_id().owner();
+            let Some(payloads) = transaction
+                .changes
+                .iter()
+                .map(|x| serde_json::from_value::<MapOpPayload>(x.clone()).ok())
+                .collect::<Option<Vec<_>>>()
+            else {
+                continue;
+            };
+            let makes_itself_admin = author == initial_admin
+                && payloads.iter().all(|x| {
+                    matches!(x, MapOpPayload::Set { key, value }
+                        if key.parse::<SessionOwner>().is_ok_and(|x| &x == author) && value == "admin")
+                });
+            let is_admin = roles
+                .role_at(author, transaction.made_at)
+                .is_some_and(|x| x.is_admin());
+            if !(is_admin || makes_itself_admin) {
+                continue;
+            }
+            for payload in payloads {
+                let MapOpPayload::Set { key, value } = payload else {
+                    continue;
+                };
+                let Some(role) = value.as_str().and_then(|x| x.parse::<Role>().ok()) else {
+                    continue;
+                };
+                let member = match key.as_str() {
+                    EVERYONE => key,
+                    _ => match key.parse::<SessionOwner>() {
+                        Ok(member) => member.to_string(),
+                        Err(_) => continue,
+                    },
+                };
+                roles
+                    .changes
+                    .entry(member)
+                    .or_default()
+                    .push((transaction.made_at, role));
+            }
+            valid.push(transaction);
+        }
+        (roles, valid)
+    }
+
+    /// The role a member had at a given time, if any.
+    pub fn role_at(&self, member: &SessionOwner, time: u64) -> Option<Role> {
+        let latest_at = |key: &str| {
+            let changes = self.changes.get(key)?;
+            let index = changes.partition_point(|(made_at, _)| *made_at <= time);
+            index.checked_sub(1).map(|x| changes[x].1)
+        };
+        latest_at(&member.to_string()).or_else(|| latest_at(EVERYONE))
+    }
+
+    /// Whether a member could make changes to [`CoValue`]s owned by the group at a given time.
+    pub fn can_write_at(&self, member: &SessionOwner, time: u64) -> bool {
+        self.role_at(member, time).is_some_and(|x| x.can_write())
+    }
+}
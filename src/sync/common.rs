@@ -40,9 +40,11 @@ pub enum SyncMessage {
         /// When retrieving a [`CoValue`] where the header (thus, group) is unknown, the server replies with [`SyncMessage::KnownStateMessage`]s
         /// and [`SyncMessage::NewContentMessage`]s of the group.\
         /// The `as_dependency_of` field signifies the group is a dependency of the [`CoValue`] being loaded, to clarify the relationship between the two.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
         as_dependency_of: Option<RawCoID>,
         /// If some [`SyncMessage::NewContentMessage`]s in a series fail to reach the server from a client, the server can inform the client it should correct its
         /// understanding of the server's state and resend the missing content.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
         is_correction: Option<bool>,
         #[serde(flatten)]
         known_state: CoValueKnownState,
@@ -53,6 +55,7 @@ pub enum SyncMessage {
     NewContentMessage {
         id: RawCoID,
         /// [`CoValueHeader`] to reply with if the header is not known to the peer.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
         header: Option<CoValueHeader>,
         priority: CoValuePriority,
         /// A list of sessions with their respective IDs and new content.
@@ -62,3 +65,137 @@ pub enum SyncMessage {
     /// Signals that a client is unsubscribing to changes made to a [`CoValue`].
     DoneMessage { id: RawCoID },
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::{
+        covalue::session::VerifiedState, crypto::encrypt::KeySecret, id::agent_id::AgentID,
+    };
+    use serde_json::{Value, json};
+    use std::collections::HashMap;
+
+    /// The first content of a map owned by a group, with one trusting and one private transaction, in the form cojson sends it.
+    ///
+    /// The map's session belongs to an account acting as the agent [`AGENT`], and its private change is encrypted with [`KEY_SECRET`].
+    pub(crate) const CONTENT_MESSAGE: &str = r#"{"action":"content","id":"co_z9DPTVJSEDToSX14GgauM9MMQaM","header":{"type":"comap","ruleset":{"type":"ownedByGroup","group":"co_zH4PEVimz8LZzpnuR9VFwPkJG2T"},"meta":null,"createdAt":"2025-03-04T10:16:02.481Z","uniqueness":"zA8vXn2KqL4pWeR7tY1m"},"priority":3,"new":{"co_zaSjB3WBb3m3JVewfu7pd2S4J69_session_z2BB9xiHvCX8":{"after":0,"newTransactions":[{"privacy":"trusting","madeAt":1741083362481,"changes":"[{\"key\":\"title\",\"op\":\"set\",\"value\":\"Groceries\"}]"},{"privacy":"private","madeAt":1741083365002,"keyUsed":"key_z7bPDuhWUBAKKV4Gu","encryptedChanges":"encrypted_UBpGr-qlmo51RgeXy62pOnGY_ijhDLIWiIB7TlA4Kt1v81NaGKb1KGO2N6T9Psw=="}],"lastSignature":"signature_z43fsa2asCK1FD8c3Pxgs7Cw4jMNc9aHHQwt5VRTaefP3NSt87oyMJ2oFVzaFBrCrUBmzDQE3xaQ35Kigmt3X9V5H"}}}"#;
+    /// The next content of the same session, sent to a peer that already knows the first two transactions.
+    const NEXT_CONTENT_MESSAGE: &str = r#"{"action":"content","id":"co_z9DPTVJSEDToSX14GgauM9MMQaM","priority":3,"new":{"co_zaSjB3WBb3m3JVewfu7pd2S4J69_session_z2BB9xiHvCX8":{"after":2,"newTransactions":[{"privacy":"trusting","madeAt":1741083371950,"changes":"[{\"key\":\"title\",\"op\":\"del\"}]"}],"lastSignature":"signature_z2ZNiFgqzPok7U21LbNEuV1epfS44CrEQFbqKeHWsiAwyhoLbdGzmMqaoNuzZ4qP5ATqBzvAaKTtx7fCyZEQ4ps96"}}}"#;
+    const AGENT: &str = "sealer_zAx5mFGETVoUAuAnKe4RewUGKFL7y2vz7TvYJaWxDMX8u/signer_z6Wz83QvwgG9Sf5EzbtJaAXrXnS1KAEDzMeZCLtthocTx";
+    const KEY_SECRET: &str = "keySecret_zLbUiWL3xVV8hTFYBVdbTNrpDo41NKS6o3LHHuDzjfcY";
+
+    fn round_trip(json: &str) -> SyncMessage {
+        let message: SyncMessage = serde_json::from_str(json).unwrap();
+        assert_eq!(serde_json::to_string(&message).unwrap(), json);
+        message
+    }
+
+    #[test]
+    fn round_trips_content_message() {
+        let SyncMessage::NewContentMessage {
+            header,
+            priority,
+            new,
+            ..
+        } = round_trip(CONTENT_MESSAGE)
+        else {
+            panic!("Expected a content message");
+        };
+        assert!(header.is_some());
+        assert_eq!(priority, CoValuePriority::Medium);
+        let session_id: SessionID = "co_zaSjB3WBb3m3JVewfu7pd2S4J69_session_z2BB9xiHvCX8"
+            .parse()
+            .unwrap();
+        assert_eq!(new.get(&session_id).unwrap().new_transactions.len(), 2);
+    }
+
+    #[test]
+    fn round_trips_content_message_without_header() {
+        // cojson leaves out the header of content for a `CoValue` the peer already knows, rather than sending `null`.
+        round_trip(NEXT_CONTENT_MESSAGE);
+    }
+
+    #[test]
+    fn round_trips_known_state_messages() {
+        round_trip(
+            r#"{"action":"load","id":"co_z9DPTVJSEDToSX14GgauM9MMQaM","header":false,"sessions":{}}"#,
+        );
+        round_trip(
+            r#"{"action":"known","id":"co_z9DPTVJSEDToSX14GgauM9MMQaM","header":true,"sessions":{"co_zaSjB3WBb3m3JVewfu7pd2S4J69_session_z2BB9xiHvCX8":3}}"#,
+        );
+        round_trip(
+            r#"{"action":"known","asDependencyOf":"co_z9DPTVJSEDToSX14GgauM9MMQaM","id":"co_zH4PEVimz8LZzpnuR9VFwPkJG2T","header":true,"sessions":{"co_zaSjB3WBb3m3JVewfu7pd2S4J69_session_z2BB9xiHvCX8":1}}"#,
+        );
+        round_trip(
+            r#"{"action":"known","isCorrection":true,"id":"co_z9DPTVJSEDToSX14GgauM9MMQaM","header":true,"sessions":{}}"#,
+        );
+        round_trip(r#"{"action":"done","id":"co_z9DPTVJSEDToSX14GgauM9MMQaM"}"#);
+    }
+
+    #[test]
+    fn round_trips_known_state_of_several_sessions() {
+        // Sessions are held in a map, so only their contents, not their order, are compared.
+        let json = r#"{"action":"known","id":"co_z9DPTVJSEDToSX14GgauM9MMQaM","header":true,"sessions":{"co_zaSjB3WBb3m3JVewfu7pd2S4J69_session_z2BB9xiHvCX8":3,"sealer_zAx5mFGETVoUAuAnKe4RewUGKFL7y2vz7TvYJaWxDMX8u/signer_z6Wz83QvwgG9Sf5EzbtJaAXrXnS1KAEDzMeZCLtthocTx_session_zH7sVq2mNcXe":5}}"#;
+        let message: SyncMessage = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            serde_json::to_value(&message).unwrap(),
+            serde_json::from_str::<Value>(json).unwrap()
+        );
+    }
+
+    #[test]
+    fn verifies_content_messages_against_the_session_signer() {
+        let content: SyncMessage = serde_json::from_str(CONTENT_MESSAGE).unwrap();
+        let next_content: SyncMessage = serde_json::from_str(NEXT_CONTENT_MESSAGE).unwrap();
+        let SyncMessage::NewContentMessage {
+            id,
+            header: Some(header),
+            ..
+        } = &content
+        else {
+            panic!("Expected a content message with a header");
+        };
+        let agent_id: AgentID = AGENT.parse().unwrap();
+        let signer_for_session =
+            |session_id: &SessionID| session_id.signer_id(|_| Ok(agent_id.signer_id().clone()));
+
+        let mut state: VerifiedState = VerifiedState::new(id, header, &DashMap::new());
+        state
+            .try_add_new_content(&content, signer_for_session)
+            .unwrap();
+        state
+            .try_add_new_content(&next_content, signer_for_session)
+            .unwrap();
+        let key_secret: KeySecret = KEY_SECRET.parse().unwrap();
+        let read_keys = HashMap::from([("key_z7bPDuhWUBAKKV4Gu".parse().unwrap(), key_secret)]);
+        let changes: Vec<_> = state
+            .valid_transactions(&read_keys)
+            .into_iter()
+            .flat_map(|x| x.changes)
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                json!({"op": "set", "key": "title", "value": "Groceries"}),
+                json!({"op": "set", "key": "note", "value": "Oat milk"}),
+                json!({"op": "del", "key": "title"}),
+            ]
+        );
+
+        // The signature of the first content does not cover the transaction added since.
+        let forged: SyncMessage = serde_json::from_str(&NEXT_CONTENT_MESSAGE.replace(
+            "signature_z2ZNiFgqzPok7U21LbNEuV1epfS44CrEQFbqKeHWsiAwyhoLbdGzmMqaoNuzZ4qP5ATqBzvAaKTtx7fCyZEQ4ps96",
+            "signature_z43fsa2asCK1FD8c3Pxgs7Cw4jMNc9aHHQwt5VRTaefP3NSt87oyMJ2oFVzaFBrCrUBmzDQE3xaQ35Kigmt3X9V5H",
+        ))
+        .unwrap();
+        let mut state: VerifiedState = VerifiedState::new(id, header, &DashMap::new());
+        state
+            .try_add_new_content(&content, signer_for_session)
+            .unwrap();
+        assert!(
+            state
+                .try_add_new_content(&forged, signer_for_session)
+                .is_err()
+        );
+    }
+}